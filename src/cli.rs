@@ -1,13 +1,31 @@
+mod authority;
 mod cancel;
+mod cancel_all;
 mod commands;
 mod create;
+mod create_ladder;
+mod list;
+mod reclaim_expired;
+mod reprice;
+mod store;
 mod take;
+mod top_up;
 mod utils;
 mod view;
+mod watch;
 
+pub use authority::*;
 pub use cancel::*;
+pub use cancel_all::*;
 pub use commands::*;
 pub use create::*;
+pub use create_ladder::*;
+pub use list::*;
+pub use reclaim_expired::*;
+pub use reprice::*;
+pub use store::*;
 pub use take::*;
+pub use top_up::*;
 pub use utils::*;
 pub use view::*;
+pub use watch::*;
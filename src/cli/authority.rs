@@ -0,0 +1,245 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use chia::{
+    bls::Signature,
+    protocol::{Bytes32, CoinSpend, Program, SpendBundle},
+    traits::Streamable,
+};
+use chia_wallet_sdk::{
+    driver::{DriverError, Spend, SpendContext},
+    types::Conditions,
+};
+use serde::{Deserialize, Serialize};
+use slot_machine::{CliError, SageClient, hex_string_to_signature};
+
+use crate::{
+    FillAuthorization, FillAuthorizer, MakerAuthority, StandardAuthority, TimelockAuthority,
+};
+
+// Clawback puzzle controlled by an external program (e.g., a vault or
+//  multisig coordinator)
+// The hook is called with 'spend' or 'sign' as its only argument and
+//  a JSON request on stdin; it answers with JSON on stdout:
+//  - spend: {"puzzle_hash", "conditions"} -> {"puzzle_reveal", "solution"}
+//  - sign: {"spend_bundle"} -> {"signature"}
+// All values are hex-encoded (CLVM is serialized, spend bundles are unsigned)
+pub struct ExternalAuthority {
+    pub puzzle_hash: Bytes32,
+    pub hook: String,
+}
+
+#[derive(Serialize)]
+struct HookSpendRequest {
+    puzzle_hash: String,
+    conditions: String,
+}
+
+#[derive(Deserialize)]
+struct HookSpendResponse {
+    puzzle_reveal: String,
+    solution: String,
+}
+
+#[derive(Serialize)]
+struct HookSignRequest {
+    spend_bundle: String,
+}
+
+#[derive(Deserialize)]
+struct HookSignResponse {
+    signature: String,
+}
+
+// Runs `hook mode` with a JSON request on stdin and parses its JSON answer
+fn call_hook<Req: Serialize, Resp: for<'de> Deserialize<'de>>(
+    hook: &str,
+    mode: &str,
+    request: &Req,
+) -> Result<Resp, String> {
+    let mut child = Command::new(hook)
+        .arg(mode)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Could not run {mode} hook: {err}"))?;
+
+    let request = serde_json::to_vec(request).map_err(|err| err.to_string())?;
+    child
+        .stdin
+        .take()
+        .ok_or(format!("Could not write to {mode} hook"))?
+        .write_all(&request)
+        .map_err(|err| format!("Could not write to {mode} hook: {err}"))?;
+
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("{mode} hook failed: {err}"))?;
+    if !output.status.success() {
+        return Err(format!("{mode} hook exited with {}", output.status));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|err| format!("Invalid {mode} hook response: {err}"))
+}
+
+impl ExternalAuthority {
+    pub fn new(puzzle_hash: Bytes32, hook: String) -> Self {
+        Self { puzzle_hash, hook }
+    }
+
+    fn call<Req: Serialize, Resp: for<'de> Deserialize<'de>>(
+        &self,
+        mode: &str,
+        request: &Req,
+    ) -> Result<Resp, String> {
+        call_hook(&self.hook, mode, request)
+    }
+
+    pub fn sign(&self, coin_spends: Vec<CoinSpend>) -> Result<Signature, CliError> {
+        let spend_bundle = SpendBundle::new(coin_spends, Signature::default())
+            .to_bytes()
+            .map_err(|err| CliError::Custom(err.to_string()))?;
+        let response: HookSignResponse = self
+            .call(
+                "sign",
+                &HookSignRequest {
+                    spend_bundle: hex::encode(spend_bundle),
+                },
+            )
+            .map_err(CliError::Custom)?;
+
+        hex_string_to_signature(&response.signature)
+    }
+}
+
+impl MakerAuthority for ExternalAuthority {
+    fn puzzle_hash(&self) -> Bytes32 {
+        self.puzzle_hash
+    }
+
+    fn spend(&self, ctx: &mut SpendContext, conditions: Conditions) -> Result<Spend, DriverError> {
+        let conditions = ctx.serialize(&conditions)?;
+        let response: HookSpendResponse = self
+            .call(
+                "spend",
+                &HookSpendRequest {
+                    puzzle_hash: hex::encode(self.puzzle_hash),
+                    conditions: hex::encode(conditions),
+                },
+            )
+            .map_err(DriverError::Custom)?;
+
+        let decode = |value: &str| {
+            hex::decode(value.trim_start_matches("0x"))
+                .map(Program::from)
+                .map_err(|err| DriverError::Custom(format!("Invalid spend hook response: {err}")))
+        };
+        let puzzle = ctx.alloc(&decode(&response.puzzle_reveal)?)?;
+        let solution = ctx.alloc(&decode(&response.solution)?)?;
+
+        if ctx.tree_hash(puzzle) != self.puzzle_hash.into() {
+            return Err(DriverError::Custom(
+                "Spend hook returned a puzzle that doesn't match the clawback puzzle hash"
+                    .to_string(),
+            ));
+        }
+
+        Ok(Spend::new(puzzle, solution))
+    }
+}
+
+// Clawback authority used by CLI commands - either the Sage wallet's
+//  standard puzzle (directly or through a recovery leaf) or an external hook
+pub enum CliMakerAuthority {
+    Sage(StandardAuthority),
+    SageRecovery(TimelockAuthority<StandardAuthority>),
+    External(ExternalAuthority),
+}
+
+impl CliMakerAuthority {
+    pub async fn sign(
+        &self,
+        sage: &SageClient,
+        coin_spends: Vec<CoinSpend>,
+    ) -> Result<Signature, CliError> {
+        match self {
+            Self::Sage(_) | Self::SageRecovery(_) => {
+                let resp = sage.sign_coin_spends(coin_spends, false, true).await?;
+                hex_string_to_signature(&resp.spend_bundle.aggregated_signature)
+            }
+            Self::External(authority) => authority.sign(coin_spends),
+        }
+    }
+}
+
+impl MakerAuthority for CliMakerAuthority {
+    fn puzzle_hash(&self) -> Bytes32 {
+        match self {
+            Self::Sage(authority) => authority.puzzle_hash(),
+            Self::SageRecovery(authority) => authority.puzzle_hash(),
+            Self::External(authority) => authority.puzzle_hash(),
+        }
+    }
+
+    fn spend(&self, ctx: &mut SpendContext, conditions: Conditions) -> Result<Spend, DriverError> {
+        match self {
+            Self::Sage(authority) => authority.spend(ctx, conditions),
+            Self::SageRecovery(authority) => authority.spend(ctx, conditions),
+            Self::External(authority) => authority.spend(ctx, conditions),
+        }
+    }
+}
+
+// Fill authorizer backed by an external program (e.g., a client for an
+//  exchange's signing service)
+// The hook is called with 'authorize' as its only argument and answers
+//  {"authorizer_key", "coin_id", "other_asset_amount", "offered_amount",
+//  "delegated_puzzle_hash", "message"} with {"signature"}
+// Keys, hashes and the AGG_SIG_ME message are hex-encoded
+pub struct ExternalFillAuthorizer {
+    pub hook: String,
+    pub agg_sig_me_additional_data: Bytes32,
+}
+
+#[derive(Serialize)]
+struct HookAuthorizeRequest {
+    authorizer_key: String,
+    coin_id: String,
+    other_asset_amount: u64,
+    offered_amount: u64,
+    delegated_puzzle_hash: String,
+    message: String,
+}
+
+impl ExternalFillAuthorizer {
+    pub fn new(hook: String, agg_sig_me_additional_data: Bytes32) -> Self {
+        Self {
+            hook,
+            agg_sig_me_additional_data,
+        }
+    }
+}
+
+impl FillAuthorizer for ExternalFillAuthorizer {
+    fn authorize(&self, authorization: &FillAuthorization) -> Result<Signature, DriverError> {
+        let response: HookSignResponse = call_hook(
+            &self.hook,
+            "authorize",
+            &HookAuthorizeRequest {
+                authorizer_key: hex::encode(authorization.authorizer_key.to_bytes()),
+                coin_id: hex::encode(authorization.coin_id),
+                other_asset_amount: authorization.other_asset_amount,
+                offered_amount: authorization.offered_amount,
+                delegated_puzzle_hash: hex::encode(authorization.delegated_puzzle_hash),
+                message: hex::encode(authorization.message(self.agg_sig_me_additional_data)),
+            },
+        )
+        .map_err(DriverError::Custom)?;
+
+        hex_string_to_signature(&response.signature)
+            .map_err(|err| DriverError::Custom(format!("Invalid authorize hook response: {err}")))
+    }
+}
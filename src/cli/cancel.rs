@@ -1,38 +1,97 @@
 use chia::protocol::SpendBundle;
 use chia_wallet_sdk::{
     coinset::ChiaRpcClient,
-    driver::{
-        Offer, Spend, SpendContext, StandardLayer, create_security_coin, decode_offer,
-        spend_security_coin,
-    },
+    driver::{Offer, SpendContext, create_security_coin, decode_offer, spend_security_coin},
     types::Conditions,
-    utils::Address,
 };
-use clvm_traits::clvm_quote;
-use clvmr::NodePtr;
 use slot_machine::{
     CliError, SageClient, assets_xch_only, get_coinset_client, get_constants, hex_string_to_pubkey,
-    hex_string_to_signature, no_assets, parse_amount, wait_for_coin,
+    no_assets, parse_amount, wait_for_coin,
 };
 
-use crate::{PartialOffer, decode_partial_offer};
+use crate::{
+    CliMakerAuthority, ExternalAuthority, MakerAuthority, PartialOffer, PartialOfferState,
+    PartialOfferStore, StandardAuthority, TimelockAuthority, current_timestamp,
+    decode_partial_offer, encode_partial_offer,
+};
 
-pub async fn cli_cancel(offer: String, fee_str: String, testnet11: bool) -> Result<(), CliError> {
+pub async fn cli_cancel(
+    offer_str: String,
+    amount_str: Option<String>,
+    spend_hook: Option<String>,
+    recovery_delay: Option<u64>,
+    fee_str: String,
+    testnet11: bool,
+) -> Result<(), CliError> {
     let fee = parse_amount(&fee_str, false)?;
     let mut ctx = SpendContext::new();
 
-    let partial_offer = PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer)?)?;
+    let client = get_coinset_client(testnet11);
+
+    let partial_offer =
+        PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer_str)?)?;
+    let (state, mut lineage) = partial_offer.sync_lineage(&client).await?;
+    let partial_offer = match state {
+        PartialOfferState::Live(partial_offer) | PartialOfferState::Expired(partial_offer) => {
+            partial_offer
+        }
+        state => {
+            return Err(CliError::Custom(format!(
+                "Partial offer can no longer be cancelled ({state})"
+            )));
+        }
+    };
 
-    let sage = SageClient::new()?;
-    let derivation_resp = &sage.get_derivations(false, 0, 1).await?.derivations[0];
-    if Address::decode(&derivation_resp.address)?.puzzle_hash
-        != partial_offer.info.maker_puzzle_hash
-    {
-        return Err(CliError::Custom(
-            "You are not the maker of this offer".to_string(),
-        ));
+    if partial_offer.info.is_clawback_locked(current_timestamp()) {
+        return Err(CliError::Custom(format!(
+            "Partial offer can't be cancelled before {}",
+            partial_offer.info.clawback_lock.unwrap_or_default()
+        )));
     }
 
+    // everything is withdrawn unless an amount is given
+    let amount = if let Some(amount_str) = amount_str {
+        parse_amount(
+            &amount_str,
+            partial_offer.info.offered_asset_info.asset_id.is_some(),
+        )?
+    } else {
+        partial_offer.coin.amount
+    };
+
+    let sage = SageClient::new()?;
+
+    // non-standard makers build (and sign) the clawback spend themselves
+    let authority = if let Some(spend_hook) = spend_hook {
+        CliMakerAuthority::External(ExternalAuthority::new(
+            partial_offer.info.clawback_puzzle_hash,
+            spend_hook,
+        ))
+    } else {
+        let derivation_resp = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+        let standard_authority =
+            StandardAuthority::new(hex_string_to_pubkey(&derivation_resp.public_key)?);
+        let authority = if let Some(recovery_delay) = recovery_delay {
+            CliMakerAuthority::SageRecovery(TimelockAuthority::recovery(
+                recovery_delay,
+                standard_authority,
+            ))
+        } else {
+            CliMakerAuthority::Sage(standard_authority)
+        };
+
+        let leaf = authority.puzzle_hash();
+        if leaf != partial_offer.info.clawback_puzzle_hash
+            && !partial_offer.info.extra_leaves.contains(&leaf)
+        {
+            return Err(CliError::Custom(
+                "You are not the maker of this offer".to_string(),
+            ));
+        }
+
+        authority
+    };
+
     let offer_resp = sage
         .make_offer(no_assets(), assets_xch_only(1), fee, None, None, false)
         .await?;
@@ -52,33 +111,44 @@ pub async fn cli_cancel(offer: String, fee_str: String, testnet11: bool) -> Resu
         get_constants(testnet11),
     )?;
 
-    let quoted_conds = clvm_quote!(Conditions::new().create_coin(
-        partial_offer.info.maker_puzzle_hash,
-        partial_offer.coin.amount,
-        ctx.hint(partial_offer.info.maker_puzzle_hash)?
-    ));
-    let inner_spend = Spend::new(ctx.alloc(&quoted_conds)?, NodePtr::NIL);
-    let inner_spend = StandardLayer::new(hex_string_to_pubkey(&derivation_resp.public_key)?)
-        .delegated_inner_spend(&mut ctx, inner_spend)?;
-    partial_offer.claw_back(&mut ctx, inner_spend)?;
+    let conditions = partial_offer.withdraw_conditions(&mut ctx, amount)?;
+    let inner_spend = authority.spend(&mut ctx, conditions)?;
+    let remaining = partial_offer.withdraw(&mut ctx, inner_spend, amount)?;
+    let new_state = if let Some(remaining) = remaining {
+        lineage.push(remaining.coin);
+        if remaining.is_expired(current_timestamp()) {
+            PartialOfferState::Expired(remaining)
+        } else {
+            PartialOfferState::Live(remaining)
+        }
+    } else {
+        PartialOfferState::ClawedBack(partial_offer.clone())
+    };
 
     let mut spends = ctx.take();
-    let resp = sage.sign_coin_spends(spends.clone(), false, true).await?;
+    let sig_from_signing = authority.sign(&sage, spends.clone()).await?;
     spends.extend(partial_offer.spend_bundle.coin_spends);
-
-    let sig_from_signing = hex_string_to_signature(&resp.spend_bundle.aggregated_signature)?;
     let sb = offer.take(SpendBundle::new(
         spends,
         security_sig + &sig_from_signing + &partial_offer.spend_bundle.aggregated_signature,
     ));
 
     println!("Submitting transaction...");
-    let client = get_coinset_client(testnet11);
     let resp = client.push_tx(sb).await?;
 
     println!("Transaction submitted; status='{}'", resp.status);
     wait_for_coin(&client, partial_offer_coin_id, true).await?;
     println!("Confirmed!");
 
+    if let PartialOfferState::Live(remaining) | PartialOfferState::Expired(remaining) = &new_state {
+        println!(
+            "Partial offer still holds {} mojos: {}",
+            remaining.coin.amount,
+            encode_partial_offer(&remaining.clone().to_spend_bundle(&mut ctx)?)?
+        );
+    }
+
+    PartialOfferStore::remember(&mut ctx, &offer_str, &new_state, &lineage, testnet11);
+
     Ok(())
 }
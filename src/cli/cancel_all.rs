@@ -0,0 +1,153 @@
+use chia::protocol::{Coin, SpendBundle};
+use chia_wallet_sdk::{
+    coinset::ChiaRpcClient,
+    driver::{Offer, SpendContext, create_security_coin, decode_offer, spend_security_coin},
+    types::Conditions,
+    utils::Address,
+};
+use slot_machine::{
+    CliError, SageClient, assets_xch_only, get_coinset_client, get_constants, hex_string_to_pubkey,
+    hex_string_to_signature, no_assets, parse_amount, wait_for_coin,
+};
+
+use crate::{
+    MakerAuthority, PartialOffer, PartialOfferState, PartialOfferStore, StandardAuthority,
+    StoredOfferStatus, current_timestamp, decode_partial_offer,
+};
+
+pub async fn cli_cancel_all(
+    offers: Vec<String>,
+    fee_str: String,
+    testnet11: bool,
+) -> Result<(), CliError> {
+    let fee = parse_amount(&fee_str, false)?;
+    let mut ctx = SpendContext::new();
+
+    let client = get_coinset_client(testnet11);
+
+    let sage = SageClient::new()?;
+    let derivation_resp = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+    let maker_puzzle_hash = Address::decode(&derivation_resp.address)?.puzzle_hash;
+
+    // open offers from the local store, plus any given explicitly
+    let mut offer_strs = PartialOfferStore::open()?
+        .load()?
+        .into_iter()
+        .filter(|record| {
+            record.testnet11 == testnet11
+                && record.maker_puzzle_hash == hex::encode(maker_puzzle_hash)
+                && matches!(
+                    record.status,
+                    StoredOfferStatus::Open | StoredOfferStatus::Expired
+                )
+        })
+        .map(|record| record.latest_offer)
+        .collect::<Vec<_>>();
+    offer_strs.extend(offers);
+
+    let mut to_cancel: Vec<(String, PartialOffer, Vec<Coin>)> = Vec::new();
+    for offer_str in offer_strs {
+        let partial_offer =
+            PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer_str)?)?;
+        // only the wallet's standard puzzle signs here, either as the clawback
+        //  puzzle or as a backup leaf
+        if partial_offer.info.clawback_puzzle_hash != maker_puzzle_hash
+            && !partial_offer.info.extra_leaves.contains(&maker_puzzle_hash)
+        {
+            println!(
+                "Skipping partial offer {}: the wallet's standard puzzle can't claw it back (offers using a spend hook or recovery leaf need 'cancel --spend-hook' or 'cancel --recovery-delay')",
+                hex::encode(partial_offer.coin.coin_id())
+            );
+            continue;
+        }
+
+        let (state, lineage) = partial_offer.sync_lineage(&client).await?;
+        match state {
+            PartialOfferState::Live(partial_offer) | PartialOfferState::Expired(partial_offer) => {
+                if partial_offer.info.is_clawback_locked(current_timestamp()) {
+                    println!("Skipping partial offer that is still locked");
+                    continue;
+                }
+
+                if to_cancel
+                    .iter()
+                    .all(|(_, known, _)| known.coin != partial_offer.coin)
+                {
+                    to_cancel.push((offer_str, partial_offer, lineage));
+                }
+            }
+            state => PartialOfferStore::remember(&mut ctx, &offer_str, &state, &lineage, testnet11),
+        }
+    }
+
+    if to_cancel.is_empty() {
+        println!("No open partial offers to cancel.");
+        return Ok(());
+    }
+    println!("Cancelling {} partial offer(s)...", to_cancel.len());
+
+    // a single offer pays the fee for every clawback
+    let offer_resp = sage
+        .make_offer(no_assets(), assets_xch_only(1), fee, None, None, false)
+        .await?;
+
+    println!("Offer {} created.", offer_resp.offer_id);
+
+    let offer = Offer::from_spend_bundle(&mut ctx, &decode_offer(&offer_resp.offer)?)?;
+
+    let (security_sk, security_coin) =
+        create_security_coin(&mut ctx, offer.offered_coins().xch[0])?;
+    let security_conditions =
+        to_cancel
+            .iter()
+            .fold(Conditions::new(), |conditions, (_, partial_offer, _)| {
+                conditions.assert_concurrent_spend(partial_offer.coin.coin_id())
+            });
+    let security_sig = spend_security_coin(
+        &mut ctx,
+        security_coin,
+        security_conditions,
+        &security_sk,
+        get_constants(testnet11),
+    )?;
+
+    let authority = StandardAuthority::new(hex_string_to_pubkey(&derivation_resp.public_key)?);
+    for (_, partial_offer, _) in &to_cancel {
+        let conditions = partial_offer.withdraw_conditions(&mut ctx, partial_offer.coin.amount)?;
+        let inner_spend = authority.spend(&mut ctx, conditions)?;
+        partial_offer.claw_back(&mut ctx, inner_spend)?;
+    }
+
+    let spends = ctx.take();
+    let resp = sage.sign_coin_spends(spends.clone(), false, true).await?;
+    let sig_from_signing = hex_string_to_signature(&resp.spend_bundle.aggregated_signature)?;
+
+    // offers that were never pushed still need their creation spends, which
+    //  may be shared (e.g., ladder rungs)
+    let sb = to_cancel.iter().fold(
+        SpendBundle::new(spends, security_sig + &sig_from_signing),
+        |sb, (_, partial_offer, _)| partial_offer.clone().take_shared(sb),
+    );
+    let sb = offer.take(sb);
+
+    println!("Submitting transaction...");
+    let resp = client.push_tx(sb).await?;
+
+    println!("Transaction submitted; status='{}'", resp.status);
+    for (_, partial_offer, _) in &to_cancel {
+        wait_for_coin(&client, partial_offer.coin.coin_id(), true).await?;
+    }
+    println!("Confirmed!");
+
+    for (offer_str, partial_offer, lineage) in to_cancel {
+        PartialOfferStore::remember(
+            &mut ctx,
+            &offer_str,
+            &PartialOfferState::ClawedBack(partial_offer),
+            &lineage,
+            testnet11,
+        );
+    }
+
+    Ok(())
+}
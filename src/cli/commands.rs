@@ -1,6 +1,9 @@
 use clap::{Parser, Subcommand};
 
-use crate::{cli_cancel, cli_create, cli_take, cli_view};
+use crate::{
+    CreateOptions, cli_cancel, cli_cancel_all, cli_create, cli_create_ladder, cli_list,
+    cli_reclaim_expired, cli_reprice, cli_take, cli_top_up, cli_view, cli_watch,
+};
 
 #[derive(Parser)]
 #[command(
@@ -32,9 +35,21 @@ enum Commands {
         #[arg(long)]
         asked_amount: String,
 
-        /// Expiration (partial offers becomes invalid after this timestamp)
+        /// Expiration - unix timestamp, RFC3339 date or duration from now (e.g., 2h30m or 7d)
         #[arg(long)]
-        expiration: Option<u64>,
+        expiration: Option<String>,
+
+        /// Block height at which the partial offer becomes invalid
+        #[arg(long)]
+        expiration_height: Option<u32>,
+
+        /// Time before which the offer can't be filled - unix timestamp, RFC3339 date or duration from now
+        #[arg(long)]
+        start: Option<String>,
+
+        /// Block height before which the offer can't be filled
+        #[arg(long)]
+        start_height: Option<u32>,
 
         /// Minimum asserted fee (paid by the taker for each fill)
         #[arg(long, default_value = "0.00042")]
@@ -44,6 +59,70 @@ enum Commands {
         #[arg(long, default_value = "0.001")]
         min_other_asset_amount: String,
 
+        /// Asked amount at the end of a Dutch auction (asked amount is the start price)
+        #[arg(long, requires_all = ["dutch_start", "dutch_end"])]
+        dutch_end_asked_amount: Option<String>,
+
+        /// Dutch auction start - unix timestamp, RFC3339 date or duration from now
+        #[arg(long, requires = "dutch_end_asked_amount")]
+        dutch_start: Option<String>,
+
+        /// Dutch auction end - unix timestamp, RFC3339 date or duration from now
+        #[arg(long, requires = "dutch_end_asked_amount")]
+        dutch_end: Option<String>,
+
+        /// Number of price steps in the Dutch auction (the price is fixed within each step)
+        #[arg(long, default_value = "10")]
+        dutch_steps: u64,
+
+        /// Bonding curve ('linear' or 'constant-product'); price then depends on the amount left
+        #[arg(long, conflicts_with = "dutch_end_asked_amount")]
+        curve: Option<String>,
+
+        /// Asked amount for the whole offered amount at the end of a linear curve (asked amount is the start price)
+        #[arg(long, requires = "curve")]
+        curve_end_asked_amount: Option<String>,
+
+        /// Let anyone send the offer back to you once it expires
+        #[arg(long, requires = "expiration")]
+        reclaimable: bool,
+
+        /// Address receiving fill proceeds (defaults to the wallet's clawback address)
+        #[arg(long)]
+        receiver_address: Option<String>,
+
+        /// Clawback address for makers with non-standard puzzles (cancel with --spend-hook)
+        #[arg(long)]
+        clawback_address: Option<String>,
+
+        /// Backup address that can also cancel the offer (can be repeated)
+        #[arg(long)]
+        backup_address: Vec<String>,
+
+        /// Recovery address that can cancel the offer once it's been untouched for a while
+        #[arg(long, requires = "recovery_delay")]
+        recovery_address: Option<String>,
+
+        /// Seconds the partial coin has to be untouched before the recovery address can cancel
+        #[arg(long, requires = "recovery_address")]
+        recovery_delay: Option<u64>,
+
+        /// Time before which the offer provably can't be cancelled (committed liquidity) - unix timestamp, RFC3339 date or duration from now
+        #[arg(long)]
+        lock_until: Option<String>,
+
+        /// Fee recipient getting a share of each fill, as 'address:bps' (can be repeated)
+        #[arg(long, conflicts_with = "curve")]
+        fee_recipient: Vec<String>,
+
+        /// Only let this taker fill the offer - public key (signs fills) or address (announces fills); can be repeated
+        #[arg(long)]
+        allow_taker: Vec<String>,
+
+        /// Public key of an authorizer (e.g., an exchange) that has to co-sign every fill
+        #[arg(long)]
+        fill_authorizer: Option<String>,
+
         /// Fee to include in partial offer
         #[arg(long, default_value = "0.00042")]
         fee: String,
@@ -52,6 +131,60 @@ enum Commands {
         #[arg(long, default_value = "false")]
         testnet11: bool,
     },
+    // Create several partial offers at stepped prices, funded by one transaction
+    CreateLadder {
+        /// Offered asset id
+        #[arg(long)]
+        offered_asset_id: Option<String>,
+
+        /// Total offered amount (split evenly across rungs)
+        #[arg(long)]
+        offered_amount: String,
+
+        /// Asked asset id
+        #[arg(long)]
+        asked_asset_id: Option<String>,
+
+        /// Lowest price (asked asset per offered asset)
+        #[arg(long)]
+        min_price: String,
+
+        /// Highest price (asked asset per offered asset)
+        #[arg(long)]
+        max_price: String,
+
+        /// Number of rungs
+        #[arg(long)]
+        rungs: u64,
+
+        /// Price spacing ('linear' or 'geometric')
+        #[arg(long, default_value = "linear")]
+        spacing: String,
+
+        /// Expiration - unix timestamp, RFC3339 date or duration from now (e.g., 2h30m or 7d)
+        #[arg(long)]
+        expiration: Option<String>,
+
+        /// Minimum asserted fee (paid by the taker for each fill)
+        #[arg(long, default_value = "0.00042")]
+        minimum_asserted_fee: Option<String>,
+
+        /// Minimum other asset amount required to accept a rung
+        #[arg(long, default_value = "0.001")]
+        min_other_asset_amount: String,
+
+        /// Address receiving fill proceeds (defaults to the wallet's clawback address)
+        #[arg(long)]
+        receiver_address: Option<String>,
+
+        /// Fee to include in the funding transaction
+        #[arg(long, default_value = "0.00042")]
+        fee: String,
+
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
     // View details of a partial offer
     View {
         /// Offer
@@ -62,16 +195,42 @@ enum Commands {
         #[arg(long, default_value = "false")]
         testnet11: bool,
     },
-    // Take a partial offer
-    Take {
+    // Print fills of a partial offer as they happen
+    Watch {
         /// Offer
         #[arg(long)]
         offer: String,
 
-        /// Amount of requested asset (the one you give) to use
+        /// Polling interval (in seconds)
+        #[arg(long, default_value = "10")]
+        interval: u64,
+
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
+    // Take one or more partial offers (best price first)
+    Take {
+        /// Offer (can be repeated)
+        #[arg(long)]
+        offer: Vec<String>,
+
+        /// File containing one offer per line
+        #[arg(long)]
+        offers_file: Option<String>,
+
+        /// Amount of requested asset (the one you give) to use across all offers
         #[arg(long)]
         amount: String,
 
+        /// Referral fee paid on top of each fill, as 'address:bps' (taken out of the amount)
+        #[arg(long)]
+        referral: Option<String>,
+
+        /// Program that co-signs fills of offers created with a fill authorizer
+        #[arg(long)]
+        authorizer_hook: Option<String>,
+
         /// Fee to include in partial offer (excl. offer required fee, if any)
         #[arg(long, default_value = "0.00042")]
         fee: String,
@@ -80,16 +239,110 @@ enum Commands {
         #[arg(long, default_value = "false")]
         testnet11: bool,
     },
+    // List partial offers created or seen by this CLI
+    List {
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
     // Cancel a partial offer
     Cancel {
         /// Offer
         #[arg(long)]
         offer: String,
 
+        /// Amount to withdraw (defaults to everything; the rest stays open)
+        #[arg(long)]
+        amount: Option<String>,
+
+        /// Program that builds and signs the clawback spend for non-standard makers
+        #[arg(long)]
+        spend_hook: Option<String>,
+
+        /// Cancel through the wallet's recovery leaf with this delay (in seconds)
+        #[arg(long, conflicts_with = "spend_hook")]
+        recovery_delay: Option<u64>,
+
         /// Fee to include in partial offer
         #[arg(long, default_value = "0.00042")]
         fee: String,
 
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
+    // Cancel all of the wallet's open partial offers in one transaction
+    CancelAll {
+        /// Additional offer to cancel (can be repeated; known open offers are always included)
+        #[arg(long)]
+        offer: Vec<String>,
+
+        /// Fee to include in the transaction
+        #[arg(long, default_value = "0.00042")]
+        fee: String,
+
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
+    // Send expired reclaimable partial offers (anyone's) back to their makers
+    ReclaimExpired {
+        /// Additional offer to reclaim (can be repeated; known expired offers are always included)
+        #[arg(long)]
+        offer: Vec<String>,
+
+        /// File with one offer per line
+        #[arg(long)]
+        offers_file: Option<String>,
+
+        /// Fee to include in the transaction (a wallet is only needed if non-zero)
+        #[arg(long, default_value = "0")]
+        fee: String,
+
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
+    // Change the price, minimum amount or expiration of a partial offer in one transaction
+    Reprice {
+        /// Offer
+        #[arg(long)]
+        offer: String,
+
+        /// New asked amount for the remaining offered amount
+        #[arg(long)]
+        asked_amount: Option<String>,
+
+        /// New minimum other asset amount required to accept the partial offer
+        #[arg(long)]
+        min_other_asset_amount: Option<String>,
+
+        /// New expiration - unix timestamp, RFC3339 date or duration from now
+        #[arg(long)]
+        expiration: Option<String>,
+
+        /// Fee to include in the transaction
+        #[arg(long, default_value = "0.00042")]
+        fee: String,
+
+        /// Use testnet11
+        #[arg(long, default_value = "false")]
+        testnet11: bool,
+    },
+    // Add more of the offered asset to a live partial offer
+    TopUp {
+        /// Offer
+        #[arg(long)]
+        offer: String,
+
+        /// Amount of the offered asset to add
+        #[arg(long)]
+        amount: String,
+
+        /// Fee to include in the transaction
+        #[arg(long, default_value = "0.00042")]
+        fee: String,
+
         /// Use testnet11
         #[arg(long, default_value = "false")]
         testnet11: bool,
@@ -110,32 +363,162 @@ pub async fn run_cli() {
             fee,
             testnet11,
             min_other_asset_amount,
+            dutch_end_asked_amount,
+            dutch_start,
+            dutch_end,
+            dutch_steps,
+            curve,
+            curve_end_asked_amount,
+            reclaimable,
+            receiver_address,
+            clawback_address,
+            backup_address,
+            recovery_address,
+            recovery_delay,
+            lock_until,
+            fee_recipient,
+            allow_taker,
+            fill_authorizer,
+            expiration_height,
+            start,
+            start_height,
         } => {
-            cli_create(
+            cli_create(CreateOptions {
                 offered_asset_id,
                 offered_amount,
                 asked_asset_id,
                 asked_amount,
                 expiration,
+                expiration_height,
+                start,
+                start_height,
+                minimum_asserted_fee,
+                min_other_asset_amount,
+                dutch_auction: dutch_end_asked_amount.map(|end_asked_amount| {
+                    (
+                        end_asked_amount,
+                        dutch_start.unwrap_or_default(),
+                        dutch_end.unwrap_or_default(),
+                        dutch_steps,
+                    )
+                }),
+                curve: curve.map(|curve| (curve, curve_end_asked_amount)),
+                reclaimable,
+                receiver_address,
+                clawback_address,
+                backup_addresses: backup_address,
+                recovery: recovery_address.zip(recovery_delay),
+                lock_until,
+                fee_recipients: fee_recipient,
+                allowed_takers: allow_taker,
+                fill_authorizer,
+                fee,
+                testnet11,
+            })
+            .await
+        }
+        Commands::CreateLadder {
+            offered_asset_id,
+            offered_amount,
+            asked_asset_id,
+            min_price,
+            max_price,
+            rungs,
+            spacing,
+            expiration,
+            minimum_asserted_fee,
+            min_other_asset_amount,
+            receiver_address,
+            fee,
+            testnet11,
+        } => {
+            cli_create_ladder(
+                offered_asset_id,
+                offered_amount,
+                asked_asset_id,
+                min_price,
+                max_price,
+                rungs,
+                spacing,
+                expiration,
                 minimum_asserted_fee,
                 fee,
                 testnet11,
                 min_other_asset_amount,
+                receiver_address,
             )
             .await
         }
         Commands::View { offer, testnet11 } => cli_view(offer, testnet11).await,
+        Commands::Watch {
+            offer,
+            interval,
+            testnet11,
+        } => cli_watch(offer, interval, testnet11).await,
         Commands::Take {
             offer,
+            offers_file,
             amount,
+            referral,
+            authorizer_hook,
             fee,
             testnet11,
-        } => cli_take(offer, amount, fee, testnet11).await,
+        } => {
+            cli_take(
+                offer,
+                offers_file,
+                amount,
+                referral,
+                authorizer_hook,
+                fee,
+                testnet11,
+            )
+            .await
+        }
+        Commands::List { testnet11 } => cli_list(testnet11).await,
         Commands::Cancel {
             offer,
+            amount,
+            spend_hook,
+            recovery_delay,
+            fee,
+            testnet11,
+        } => cli_cancel(offer, amount, spend_hook, recovery_delay, fee, testnet11).await,
+        Commands::CancelAll {
+            offer,
+            fee,
+            testnet11,
+        } => cli_cancel_all(offer, fee, testnet11).await,
+        Commands::ReclaimExpired {
+            offer,
+            offers_file,
+            fee,
+            testnet11,
+        } => cli_reclaim_expired(offer, offers_file, fee, testnet11).await,
+        Commands::Reprice {
+            offer,
+            asked_amount,
+            min_other_asset_amount,
+            expiration,
+            fee,
+            testnet11,
+        } => {
+            cli_reprice(
+                offer,
+                asked_amount,
+                min_other_asset_amount,
+                expiration,
+                fee,
+                testnet11,
+            )
+            .await
+        }
+        Commands::TopUp {
+            offer,
+            amount,
             fee,
             testnet11,
-        } => cli_cancel(offer, fee, testnet11).await,
+        } => cli_top_up(offer, amount, fee, testnet11).await,
     };
 
     if let Err(err) = res {
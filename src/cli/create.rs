@@ -19,21 +19,94 @@ use slot_machine::{
 };
 
 use crate::{
-    PartialOffer, PartialOfferAssetInfo, PartialOfferInfo, PartialPriceData, encode_partial_offer,
+    FEE_SPLIT_BPS_DENOMINATOR, MAX_DUTCH_STEPS, PartialCurve, PartialDutchAuction,
+    PartialFeeRecipient, PartialOffer, PartialOfferAssetInfo, PartialOfferInfo, PartialOfferState,
+    PartialOfferStore, PartialPriceData, PartialTakerAllowlist, current_timestamp,
+    encode_partial_offer, parse_timestamp,
 };
 
-#[allow(clippy::too_many_arguments)]
-pub async fn cli_create(
-    offered_asset_id_str: Option<String>,
-    offered_amount_str: String,
-    asked_asset_id_str: Option<String>,
-    asked_amount_str: String,
-    expiration: Option<u64>,
-    minimum_asserted_fee: Option<String>,
-    fee_str: String,
-    testnet11: bool,
-    min_other_asset_amount: String,
-) -> Result<(), CliError> {
+// Arguments of `create`; amounts, times and addresses are parsed by `cli_create`
+pub struct CreateOptions {
+    pub offered_asset_id: Option<String>,
+    pub offered_amount: String,
+    pub asked_asset_id: Option<String>,
+    pub asked_amount: String,
+    pub expiration: Option<String>,
+    pub expiration_height: Option<u32>,
+    pub start: Option<String>,
+    pub start_height: Option<u32>,
+    pub minimum_asserted_fee: Option<String>,
+    pub min_other_asset_amount: String,
+    // (end asked amount, start, end, steps)
+    pub dutch_auction: Option<(String, String, String, u64)>,
+    // (curve, end asked amount)
+    pub curve: Option<(String, Option<String>)>,
+    pub reclaimable: bool,
+    pub receiver_address: Option<String>,
+    pub clawback_address: Option<String>,
+    pub backup_addresses: Vec<String>,
+    // (address, delay)
+    pub recovery: Option<(String, u64)>,
+    pub lock_until: Option<String>,
+    pub fee_recipients: Vec<String>,
+    pub allowed_takers: Vec<String>,
+    pub fill_authorizer: Option<String>,
+    pub fee: String,
+    pub testnet11: bool,
+}
+
+pub async fn cli_create(options: CreateOptions) -> Result<(), CliError> {
+    let CreateOptions {
+        offered_asset_id: offered_asset_id_str,
+        offered_amount: offered_amount_str,
+        asked_asset_id: asked_asset_id_str,
+        asked_amount: asked_amount_str,
+        expiration: expiration_str,
+        expiration_height,
+        start: start_str,
+        start_height,
+        minimum_asserted_fee,
+        min_other_asset_amount,
+        dutch_auction,
+        curve,
+        reclaimable,
+        receiver_address,
+        clawback_address,
+        backup_addresses,
+        recovery,
+        lock_until: lock_until_str,
+        fee_recipients,
+        allowed_takers,
+        fill_authorizer: fill_authorizer_str,
+        fee: fee_str,
+        testnet11,
+    } = options;
+
+    let now = current_timestamp();
+    let expiration = expiration_str
+        .map(|expiration_str| parse_timestamp(&expiration_str, now))
+        .transpose()?;
+    let start_time = start_str
+        .map(|start_str| parse_timestamp(&start_str, now))
+        .transpose()?;
+    let lock_until = lock_until_str
+        .map(|lock_until_str| parse_timestamp(&lock_until_str, now))
+        .transpose()?;
+    if let (Some(start_time), Some(expiration)) = (start_time, expiration)
+        && start_time >= expiration
+    {
+        return Err(CliError::Custom(
+            "Offer needs to start before it expires".to_string(),
+        ));
+    }
+    if let (Some(start_height), Some(expiration_height)) = (start_height, expiration_height)
+        && start_height >= expiration_height
+    {
+        return Err(CliError::Custom(
+            "Offer needs to start before its expiration height".to_string(),
+        ));
+    }
+
     let offered_asset_id = if let Some(offered_asset_id_str) = &offered_asset_id_str {
         Some(hex_string_to_bytes32(offered_asset_id_str)?)
     } else {
@@ -58,6 +131,144 @@ pub async fn cli_create(
     let asked_amount = parse_amount(&asked_amount_str, asked_asset_id.is_some())?;
     let fee = parse_amount(&fee_str, false)?;
 
+    let dutch_auction = if let Some((end_asked_amount_str, start_time_str, end_time_str, steps)) =
+        dutch_auction
+    {
+        let start_time = parse_timestamp(&start_time_str, now)?;
+        let end_time = parse_timestamp(&end_time_str, now)?;
+        if start_time >= end_time || steps < 2 {
+            return Err(CliError::Custom(
+                "Dutch auction needs to end after it starts and have at least 2 steps".to_string(),
+            ));
+        }
+        if steps > MAX_DUTCH_STEPS {
+            return Err(CliError::Custom(format!(
+                "Dutch auction can't have more than {MAX_DUTCH_STEPS} steps"
+            )));
+        }
+        if expiration.is_some_and(|expiration| expiration <= end_time) {
+            return Err(CliError::Custom(
+                "Dutch auction needs to end before the offer expires".to_string(),
+            ));
+        }
+
+        Some(PartialDutchAuction {
+            start_time,
+            end_time,
+            end_precision: parse_amount(&end_asked_amount_str, asked_asset_id.is_some())?,
+            steps,
+        })
+    } else {
+        None
+    };
+
+    // the asked amount is the starting price in both cases
+    let curve = if let Some((curve_str, end_asked_amount_str)) = curve {
+        match curve_str.as_str() {
+            "linear" => Some(PartialCurve::linear(
+                offered_amount,
+                offered_amount,
+                asked_amount,
+                if let Some(end_asked_amount_str) = end_asked_amount_str {
+                    parse_amount(&end_asked_amount_str, asked_asset_id.is_some())?
+                } else {
+                    asked_amount
+                },
+            )),
+            "constant-product" => {
+                if end_asked_amount_str.is_some() {
+                    return Err(CliError::Custom(
+                        "Constant product curves don't have an end price".to_string(),
+                    ));
+                }
+
+                println!("Note: constant product offers can never be fully filled");
+                Some(PartialCurve::constant_product(
+                    offered_amount as u128 * asked_amount as u128,
+                ))
+            }
+            _ => {
+                return Err(CliError::Custom(
+                    "Curve must be either 'linear' or 'constant-product'".to_string(),
+                ));
+            }
+        }
+    } else {
+        None
+    };
+
+    let receiver_puzzle_hash = if let Some(receiver_address) = &receiver_address {
+        Some(Address::decode(receiver_address)?.puzzle_hash)
+    } else {
+        None
+    };
+
+    // backup keys can claw back at any time, recovery keys only after a delay
+    let mut extra_leaves = backup_addresses
+        .iter()
+        .map(|address| Ok(Address::decode(address)?.puzzle_hash))
+        .collect::<Result<Vec<_>, CliError>>()?;
+    if let Some((recovery_address, recovery_delay)) = recovery {
+        extra_leaves.push(PartialOfferInfo::recovery_leaf(
+            recovery_delay,
+            Address::decode(&recovery_address)?.puzzle_hash,
+        ));
+    }
+
+    let fee_recipients = fee_recipients
+        .iter()
+        .map(|fee_recipient| {
+            let Some((address, bps)) = fee_recipient.rsplit_once(':') else {
+                return Err(CliError::Custom(
+                    "Fee recipients need to be given as 'address:bps'".to_string(),
+                ));
+            };
+            let bps = bps
+                .parse::<u64>()
+                .map_err(|_| CliError::Custom(format!("Invalid basis points: {bps}")))?;
+
+            Ok(PartialFeeRecipient {
+                puzzle_hash: Address::decode(address)?.puzzle_hash,
+                bps,
+            })
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
+    if !PartialFeeRecipient::are_valid(&fee_recipients) {
+        return Err(CliError::Custom(format!(
+            "Fee recipients must be unique and get less than {FEE_SPLIT_BPS_DENOMINATOR} bps in total"
+        )));
+    }
+    if curve.is_some() && !fee_recipients.is_empty() {
+        return Err(CliError::Custom(
+            "Bonding curves don't support fee recipients".to_string(),
+        ));
+    }
+
+    // takers are given as public keys (AGG_SIG_ME) or addresses (announcements)
+    let taker_allowlist = if allowed_takers.is_empty() {
+        None
+    } else {
+        let mut taker_allowlist = PartialTakerAllowlist::default();
+        for allowed_taker in &allowed_takers {
+            if let Ok(address) = Address::decode(allowed_taker) {
+                taker_allowlist.puzzle_hashes.push(address.puzzle_hash);
+            } else {
+                taker_allowlist
+                    .keys
+                    .push(hex_string_to_pubkey(allowed_taker)?);
+            }
+        }
+
+        println!("Only allowlisted takers will be able to fill this offer");
+        Some(taker_allowlist)
+    };
+    let fill_authorizer = if let Some(fill_authorizer_str) = &fill_authorizer_str {
+        println!("Every fill will need to be co-signed by the fill authorizer");
+        Some(hex_string_to_pubkey(fill_authorizer_str)?)
+    } else {
+        None
+    };
+
     let sage = SageClient::new()?;
 
     let one_sided_offer = sage
@@ -76,19 +287,30 @@ pub async fn cli_create(
         .await?;
     println!("One-sided offer {} created.", one_sided_offer.offer_id);
 
-    let data = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+    // non-standard makers (vaults, multisigs) provide their own clawback
+    //  puzzle hash and cancel through a spend hook
+    let (clawback_address, maker_puzzle_hash) = if let Some(clawback_address) = clawback_address {
+        let maker_puzzle_hash = Address::decode(&clawback_address)?.puzzle_hash;
+        (clawback_address, maker_puzzle_hash)
+    } else {
+        let data = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+        let maker_puzzle_hash = Address::decode(&data.address)?.puzzle_hash;
+        let maker_pk = hex_string_to_pubkey(&data.public_key)?;
+
+        if StandardArgs::curry_tree_hash(maker_pk) != maker_puzzle_hash.into() {
+            return Err(CliError::Custom(
+                "Maker uses non-standard puzzle (provide a clawback address instead)".to_string(),
+            ));
+        }
+
+        (data.address.clone(), maker_puzzle_hash)
+    };
     println!(
         "Will use the following address for clawback: {}",
-        data.address
+        clawback_address
     );
-
-    let maker_puzzle_hash = Address::decode(&data.address)?.puzzle_hash;
-    let maker_pk = hex_string_to_pubkey(&data.public_key)?;
-
-    if StandardArgs::curry_tree_hash(maker_pk) != maker_puzzle_hash.into() {
-        return Err(CliError::Custom(
-            "Maker uses non-standard puzzle".to_string(),
-        ));
+    if let Some(receiver_address) = receiver_address {
+        println!("Fill proceeds will go to: {}", receiver_address);
     }
 
     let mut ctx = SpendContext::new();
@@ -129,7 +351,19 @@ pub async fn cli_create(
         },
         price_data,
         min_other_asset_amount_minus_one,
-    );
+    )
+    .with_dutch_auction(dutch_auction)
+    .with_curve(curve)
+    .with_reclaimable(reclaimable)
+    .with_receiver_puzzle_hash(receiver_puzzle_hash.unwrap_or(maker_puzzle_hash))
+    .with_extra_leaves(extra_leaves)
+    .with_clawback_lock(lock_until)
+    .with_fee_recipients(fee_recipients)
+    .with_taker_allowlist(taker_allowlist)
+    .with_fill_authorizer(fill_authorizer)
+    .with_start_time(start_time)
+    .with_start_height(start_height)
+    .with_expiration_height(expiration_height);
 
     let (security_sk, security_coin) =
         create_security_coin(&mut ctx, offer.offered_coins().xch[0])?;
@@ -137,7 +371,7 @@ pub async fn cli_create(
 
     let offer_mod = ctx.alloc_mod::<SettlementPayment>()?;
     let partial_offer_payment = Payment::new(
-        partial_offer_info.inner_puzzle_hash().into(),
+        partial_offer_info.inner_puzzle_hash()?.into(),
         offered_amount,
         Memos::None,
     );
@@ -190,7 +424,10 @@ pub async fn cli_create(
     } else {
         offer.offered_coins().xch[0].coin_id()
     };
-    let partial_offer = PartialOffer::new(parent_coin_id, offered_amount, partial_offer_info);
+    let partial_offer = PartialOffer::new(parent_coin_id, offered_amount, partial_offer_info)?;
+
+    let partial_offer_coin = partial_offer.coin;
+    let partial_offer_state = PartialOfferState::Live(partial_offer.clone());
 
     let mut coin_spends = partial_offer.to_spend_bundle(&mut ctx)?.coin_spends;
     coin_spends.extend(ctx.take());
@@ -199,7 +436,16 @@ pub async fn cli_create(
     coin_spends.extend(offer.spend_bundle().coin_spends.to_vec());
     let sb = SpendBundle::new(coin_spends, sig);
 
-    println!("Partial offer: {:}", encode_partial_offer(&sb)?);
+    let partial_offer_str = encode_partial_offer(&sb)?;
+    println!("Partial offer: {:}", partial_offer_str);
+
+    PartialOfferStore::remember(
+        &mut ctx,
+        &partial_offer_str,
+        &partial_offer_state,
+        &[partial_offer_coin],
+        testnet11,
+    );
 
     Ok(())
 }
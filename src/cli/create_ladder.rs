@@ -0,0 +1,265 @@
+use chia::protocol::{Bytes32, SpendBundle};
+use chia_puzzle_types::{
+    Memos,
+    offer::{NotarizedPayment, Payment, SettlementPaymentsSolution},
+    standard::StandardArgs,
+};
+use chia_wallet_sdk::{
+    driver::{
+        Cat, CatSpend, Offer, Spend, SpendContext, create_security_coin, decode_offer,
+        spend_security_coin,
+    },
+    types::{Conditions, puzzles::SettlementPayment},
+    utils::Address,
+};
+use clvmr::NodePtr;
+use slot_machine::{
+    CliError, SageClient, assets_xch_and_cat, assets_xch_only, get_constants,
+    hex_string_to_bytes32, hex_string_to_pubkey, no_assets, parse_amount,
+};
+
+use crate::{
+    LadderFunding, LadderSpacing, PartialOffer, PartialOfferAssetInfo, PartialOfferInfo,
+    PartialOfferState, PartialOfferStore, current_timestamp, encode_partial_offer, ladder_amounts,
+    ladder_price_data, parse_timestamp,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn cli_create_ladder(
+    offered_asset_id_str: Option<String>,
+    offered_amount_str: String,
+    asked_asset_id_str: Option<String>,
+    min_price_str: String,
+    max_price_str: String,
+    rungs: u64,
+    spacing_str: String,
+    expiration_str: Option<String>,
+    minimum_asserted_fee: Option<String>,
+    fee_str: String,
+    testnet11: bool,
+    min_other_asset_amount: String,
+    receiver_address: Option<String>,
+) -> Result<(), CliError> {
+    let expiration = expiration_str
+        .map(|expiration_str| parse_timestamp(&expiration_str, current_timestamp()))
+        .transpose()?;
+
+    let offered_asset_id = if let Some(offered_asset_id_str) = &offered_asset_id_str {
+        Some(hex_string_to_bytes32(offered_asset_id_str)?)
+    } else {
+        None
+    };
+
+    let asked_asset_id = if let Some(asked_asset_id_str) = &asked_asset_id_str {
+        Some(hex_string_to_bytes32(asked_asset_id_str)?)
+    } else {
+        None
+    };
+
+    if offered_asset_id == asked_asset_id {
+        return Err(CliError::Custom(
+            "Do you actually want to ask and offer the same asset?".to_string(),
+        ));
+    }
+
+    let spacing = match spacing_str.as_str() {
+        "linear" => LadderSpacing::Linear,
+        "geometric" => LadderSpacing::Geometric,
+        _ => {
+            return Err(CliError::Custom(
+                "Spacing must be either 'linear' or 'geometric'".to_string(),
+            ));
+        }
+    };
+
+    let min_other_asset_amount_minus_one = parse_amount(&min_other_asset_amount, true)? - 1;
+    let offered_amount = parse_amount(&offered_amount_str, offered_asset_id.is_some())?;
+    let fee = parse_amount(&fee_str, false)?;
+
+    // prices are given in asked asset per offered asset (both in regular units)
+    let unit = parse_amount("1", offered_asset_id.is_some())?;
+    let min_price = parse_amount(&min_price_str, asked_asset_id.is_some())?;
+    let max_price = parse_amount(&max_price_str, asked_asset_id.is_some())?;
+
+    if rungs == 0 || offered_amount < rungs {
+        return Err(CliError::Custom(
+            "Offered amount needs to cover at least one mojo per rung".to_string(),
+        ));
+    }
+    if min_price == 0 || min_price > max_price {
+        return Err(CliError::Custom(
+            "Prices must be positive and min price can't exceed max price".to_string(),
+        ));
+    }
+
+    let receiver_puzzle_hash = if let Some(receiver_address) = &receiver_address {
+        Some(Address::decode(receiver_address)?.puzzle_hash)
+    } else {
+        None
+    };
+
+    let sage = SageClient::new()?;
+
+    let one_sided_offer = sage
+        .make_offer(
+            no_assets(),
+            if let Some(offered_asset_id_str) = offered_asset_id_str {
+                assets_xch_and_cat(1, offered_asset_id_str, offered_amount)
+            } else {
+                assets_xch_only(offered_amount + 1)
+            },
+            fee,
+            None,
+            None,
+            true,
+        )
+        .await?;
+    println!("One-sided offer {} created.", one_sided_offer.offer_id);
+
+    let data = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+    println!(
+        "Will use the following address for clawback: {}",
+        data.address
+    );
+    if let Some(receiver_address) = receiver_address {
+        println!("Fill proceeds will go to: {}", receiver_address);
+    }
+
+    let maker_puzzle_hash = Address::decode(&data.address)?.puzzle_hash;
+    let maker_pk = hex_string_to_pubkey(&data.public_key)?;
+
+    if StandardArgs::curry_tree_hash(maker_pk) != maker_puzzle_hash.into() {
+        return Err(CliError::Custom(
+            "Maker uses non-standard puzzle".to_string(),
+        ));
+    }
+
+    let mut ctx = SpendContext::new();
+
+    let offer = Offer::from_spend_bundle(&mut ctx, &decode_offer(&one_sided_offer.offer)?)?;
+
+    let requested_asset_info = if let Some(asked_asset_id) = asked_asset_id {
+        PartialOfferAssetInfo::cat(asked_asset_id, None)
+    } else {
+        PartialOfferAssetInfo::xch()
+    };
+
+    let offered_asset_info = if let Some(offered_asset_id) = offered_asset_id {
+        PartialOfferAssetInfo::cat(offered_asset_id, None)
+    } else {
+        PartialOfferAssetInfo::xch()
+    };
+
+    let required_fee = if let Some(minimum_asserted_fee) = minimum_asserted_fee {
+        Some(parse_amount(&minimum_asserted_fee, false)?)
+    } else {
+        None
+    };
+
+    let rungs = ladder_price_data(unit, min_price, max_price, rungs, spacing)
+        .into_iter()
+        .zip(ladder_amounts(offered_amount, rungs))
+        .map(|(price_data, amount)| {
+            (
+                PartialOfferInfo::new(
+                    None,
+                    offered_asset_info,
+                    requested_asset_info,
+                    maker_puzzle_hash,
+                    expiration,
+                    required_fee,
+                    price_data,
+                    min_other_asset_amount_minus_one,
+                )
+                .with_receiver_puzzle_hash(receiver_puzzle_hash.unwrap_or(maker_puzzle_hash)),
+                amount,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let (security_sk, security_coin) =
+        create_security_coin(&mut ctx, offer.offered_coins().xch[0])?;
+    let _ = ctx.take(); // ignore coin spend inserted by func above
+
+    let offer_mod = ctx.alloc_mod::<SettlementPayment>()?;
+    let rung_payments = PartialOffer::ladder_funding_payments(&mut ctx, &rungs)?;
+    let security_coin_payment =
+        Payment::new(security_coin.puzzle_hash, security_coin.amount, Memos::None);
+    let xch_offer_solution = ctx.alloc(&SettlementPaymentsSolution {
+        notarized_payments: vec![NotarizedPayment::new(
+            Bytes32::default(),
+            if offered_asset_id.is_none() {
+                [rung_payments.clone(), vec![security_coin_payment]].concat()
+            } else {
+                vec![security_coin_payment]
+            },
+        )],
+    })?;
+    ctx.spend(
+        offer.offered_coins().xch[0],
+        Spend::new(offer_mod, xch_offer_solution),
+    )?;
+
+    let funding = if let Some(offered_asset_id) = offered_asset_id {
+        let cat = offer.offered_coins().cats.get(&offered_asset_id).unwrap()[0];
+        let cat_offer_solution = ctx.alloc(&SettlementPaymentsSolution {
+            notarized_payments: vec![NotarizedPayment::new(Bytes32::default(), rung_payments)],
+        })?;
+        let _ = Cat::spend_all(
+            &mut ctx,
+            &[CatSpend::new(
+                cat,
+                Spend::new(offer_mod, cat_offer_solution),
+            )],
+        )?;
+
+        LadderFunding::Cat(cat)
+    } else {
+        LadderFunding::Xch(offer.offered_coins().xch[0].coin_id())
+    };
+
+    let partial_offers = PartialOffer::fund_ladder(&mut ctx, funding, rungs)?;
+
+    let security_sig = spend_security_coin(
+        &mut ctx,
+        security_coin,
+        Conditions::new().remark(NodePtr::NIL),
+        &security_sk,
+        get_constants(testnet11),
+    )?;
+
+    // every rung carries the same spends - the first fill or cancellation
+    //  creates all of them
+    let mut coin_spends = ctx.take();
+    coin_spends.extend(offer.spend_bundle().coin_spends.to_vec());
+    let sb = SpendBundle::new(
+        coin_spends,
+        security_sig + &offer.spend_bundle().aggregated_signature,
+    );
+
+    for partial_offer in partial_offers {
+        let partial_offer = PartialOffer {
+            spend_bundle: sb.clone(),
+            ..partial_offer
+        };
+        let price = partial_offer.info.price_data.precision;
+        let partial_offer_coin = partial_offer.coin;
+        let partial_offer_state = PartialOfferState::Live(partial_offer.clone());
+
+        let partial_offer_str = encode_partial_offer(&partial_offer.to_spend_bundle(&mut ctx)?)?;
+        println!(
+            "Rung offering {} mojos at {} mojos per {} mojos: {}",
+            partial_offer_coin.amount, price, unit, partial_offer_str
+        );
+
+        PartialOfferStore::remember(
+            &mut ctx,
+            &partial_offer_str,
+            &partial_offer_state,
+            &[partial_offer_coin],
+            testnet11,
+        );
+    }
+
+    Ok(())
+}
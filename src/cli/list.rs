@@ -0,0 +1,34 @@
+use slot_machine::CliError;
+
+use crate::PartialOfferStore;
+
+pub async fn cli_list(testnet11: bool) -> Result<(), CliError> {
+    let records = PartialOfferStore::open()?.load()?;
+
+    let mut found = false;
+    for record in records
+        .iter()
+        .filter(|record| record.testnet11 == testnet11)
+    {
+        found = true;
+
+        let Some(latest_coin) = record.lineage.last() else {
+            continue;
+        };
+        println!(
+            "[{:?}] coin {} - remaining: {} mojos, fills: {}",
+            record.status,
+            latest_coin.coin_id,
+            record.remaining_amount(),
+            record.fills().len()
+        );
+        println!("  Maker puzzle hash: {}", record.maker_puzzle_hash);
+        println!("  Latest offer: {}", record.latest_offer);
+    }
+
+    if !found {
+        println!("No known partial offers.");
+    }
+
+    Ok(())
+}
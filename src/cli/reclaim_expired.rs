@@ -0,0 +1,151 @@
+use chia::{
+    bls::Signature,
+    protocol::{Coin, SpendBundle},
+};
+use chia_wallet_sdk::{
+    coinset::ChiaRpcClient,
+    driver::{Offer, SpendContext, create_security_coin, decode_offer, spend_security_coin},
+    types::Conditions,
+};
+use slot_machine::{
+    CliError, SageClient, assets_xch_only, get_coinset_client, get_constants, no_assets,
+    parse_amount, wait_for_coin,
+};
+
+use crate::{
+    PartialOffer, PartialOfferState, PartialOfferStore, StoredOfferStatus, decode_partial_offer,
+};
+
+pub async fn cli_reclaim_expired(
+    offers: Vec<String>,
+    offers_file: Option<String>,
+    fee_str: String,
+    testnet11: bool,
+) -> Result<(), CliError> {
+    let fee = parse_amount(&fee_str, false)?;
+    let mut ctx = SpendContext::new();
+
+    let client = get_coinset_client(testnet11);
+
+    // expired offers from the local store, plus any given explicitly
+    let mut offer_strs = PartialOfferStore::open()?
+        .load()?
+        .into_iter()
+        .filter(|record| {
+            record.testnet11 == testnet11 && matches!(record.status, StoredOfferStatus::Expired)
+        })
+        .map(|record| record.latest_offer)
+        .collect::<Vec<_>>();
+    offer_strs.extend(offers);
+    if let Some(offers_file) = offers_file {
+        let contents = std::fs::read_to_string(offers_file)
+            .map_err(|err| CliError::Custom(format!("Could not read offers file: {err}")))?;
+        offer_strs.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    let mut to_reclaim: Vec<(String, PartialOffer, Vec<Coin>)> = Vec::new();
+    for offer_str in offer_strs {
+        let partial_offer =
+            PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer_str)?)?;
+
+        let (state, lineage) = partial_offer.sync_lineage(&client).await?;
+        match state {
+            PartialOfferState::Expired(partial_offer) => {
+                if partial_offer.info.reclaim_args().is_none() {
+                    println!("Skipping expired partial offer without a reclaim leaf");
+                    continue;
+                }
+
+                if to_reclaim
+                    .iter()
+                    .all(|(_, known, _)| known.coin != partial_offer.coin)
+                {
+                    to_reclaim.push((offer_str, partial_offer, lineage));
+                }
+            }
+            state => PartialOfferStore::remember(&mut ctx, &offer_str, &state, &lineage, testnet11),
+        }
+    }
+
+    if to_reclaim.is_empty() {
+        println!("No expired partial offers to reclaim.");
+        return Ok(());
+    }
+    println!("Reclaiming {} partial offer(s)...", to_reclaim.len());
+
+    for (_, partial_offer, _) in &to_reclaim {
+        partial_offer.reclaim(&mut ctx)?;
+    }
+
+    // reclaim spends don't need signatures, so a wallet is only needed to pay
+    //  the fee
+    let mut sb = if fee > 0 {
+        let sage = SageClient::new()?;
+        let offer_resp = sage
+            .make_offer(no_assets(), assets_xch_only(1), fee, None, None, false)
+            .await?;
+        println!("Offer {} created.", offer_resp.offer_id);
+
+        let offer = Offer::from_spend_bundle(&mut ctx, &decode_offer(&offer_resp.offer)?)?;
+
+        let (security_sk, security_coin) =
+            create_security_coin(&mut ctx, offer.offered_coins().xch[0])?;
+        let security_conditions =
+            to_reclaim
+                .iter()
+                .fold(Conditions::new(), |conditions, (_, partial_offer, _)| {
+                    conditions.assert_concurrent_spend(partial_offer.coin.coin_id())
+                });
+        let security_sig = spend_security_coin(
+            &mut ctx,
+            security_coin,
+            security_conditions,
+            &security_sk,
+            get_constants(testnet11),
+        )?;
+
+        offer.take(SpendBundle::new(ctx.take(), security_sig))
+    } else {
+        SpendBundle::new(ctx.take(), Signature::default())
+    };
+
+    // offers that were never pushed still need their creation spends, which
+    //  may be shared (e.g., ladder rungs)
+    for (_, partial_offer, _) in &to_reclaim {
+        if !partial_offer
+            .spend_bundle
+            .coin_spends
+            .first()
+            .is_some_and(|coin_spend| sb.coin_spends.contains(coin_spend))
+        {
+            sb = partial_offer.clone().take(sb);
+        }
+    }
+
+    println!("Submitting transaction...");
+    let resp = client.push_tx(sb).await?;
+
+    println!("Transaction submitted; status='{}'", resp.status);
+    for (_, partial_offer, _) in &to_reclaim {
+        wait_for_coin(&client, partial_offer.coin.coin_id(), true).await?;
+    }
+    println!("Confirmed!");
+
+    for (offer_str, partial_offer, lineage) in to_reclaim {
+        PartialOfferStore::remember(
+            &mut ctx,
+            &offer_str,
+            &PartialOfferState::ClawedBack(partial_offer),
+            &lineage,
+            testnet11,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use chia::protocol::SpendBundle;
+use chia_wallet_sdk::{
+    coinset::ChiaRpcClient,
+    driver::{
+        Offer, Spend, SpendContext, StandardLayer, create_security_coin, decode_offer,
+        spend_security_coin,
+    },
+    types::Conditions,
+    utils::Address,
+};
+use clvm_traits::clvm_quote;
+use clvmr::NodePtr;
+use slot_machine::{
+    CliError, SageClient, assets_xch_only, get_coinset_client, get_constants, hex_string_to_pubkey,
+    hex_string_to_signature, no_assets, parse_amount, wait_for_coin,
+};
+
+use crate::{
+    PartialOffer, PartialOfferInfo, PartialOfferState, PartialOfferStore, PartialPriceData,
+    current_timestamp, decode_partial_offer, encode_partial_offer, parse_timestamp,
+};
+
+pub async fn cli_reprice(
+    offer_str: String,
+    asked_amount_str: Option<String>,
+    min_other_asset_amount: Option<String>,
+    expiration_str: Option<String>,
+    fee_str: String,
+    testnet11: bool,
+) -> Result<(), CliError> {
+    let fee = parse_amount(&fee_str, false)?;
+    let expiration = expiration_str
+        .map(|expiration_str| parse_timestamp(&expiration_str, current_timestamp()))
+        .transpose()?;
+    let mut ctx = SpendContext::new();
+
+    let client = get_coinset_client(testnet11);
+
+    let partial_offer =
+        PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer_str)?)?;
+    let (state, lineage) = partial_offer.sync_lineage(&client).await?;
+    let partial_offer = match state {
+        PartialOfferState::Live(partial_offer) | PartialOfferState::Expired(partial_offer) => {
+            partial_offer
+        }
+        state => {
+            return Err(CliError::Custom(format!(
+                "Partial offer can no longer be repriced ({state})"
+            )));
+        }
+    };
+
+    // asked amount is for everything that's left in the coin
+    let price_data = if let Some(asked_amount_str) = asked_amount_str {
+        if partial_offer.info.curve.is_some() {
+            return Err(CliError::Custom(
+                "Bonding-curve offers can't be given a new asked amount".to_string(),
+            ));
+        }
+
+        PartialPriceData {
+            price_precision: partial_offer.coin.amount,
+            precision: parse_amount(
+                &asked_amount_str,
+                partial_offer.info.requested_asset_info.asset_id.is_some(),
+            )?,
+        }
+    } else {
+        partial_offer.info.price_data
+    };
+    let min_other_asset_amount_minus_one =
+        if let Some(min_other_asset_amount) = min_other_asset_amount {
+            parse_amount(&min_other_asset_amount, true)? - 1
+        } else {
+            partial_offer.info.min_other_asset_amount_minus_one
+        };
+    let new_info = PartialOfferInfo {
+        price_data,
+        min_other_asset_amount_minus_one,
+        expiration: expiration.or(partial_offer.info.expiration),
+        ..partial_offer.info.clone()
+    };
+
+    let sage = SageClient::new()?;
+    let derivation_resp = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+    if Address::decode(&derivation_resp.address)?.puzzle_hash
+        != partial_offer.info.clawback_puzzle_hash
+    {
+        return Err(CliError::Custom(
+            "You are not the maker of this offer".to_string(),
+        ));
+    }
+
+    let offer_resp = sage
+        .make_offer(no_assets(), assets_xch_only(1), fee, None, None, false)
+        .await?;
+
+    println!("Offer {} created.", offer_resp.offer_id);
+
+    let partial_offer_coin_id = partial_offer.coin.coin_id();
+    let offer = Offer::from_spend_bundle(&mut ctx, &decode_offer(&offer_resp.offer)?)?;
+
+    let (security_sk, security_coin) =
+        create_security_coin(&mut ctx, offer.offered_coins().xch[0])?;
+    let security_sig = spend_security_coin(
+        &mut ctx,
+        security_coin,
+        Conditions::new().assert_concurrent_spend(partial_offer_coin_id),
+        &security_sk,
+        get_constants(testnet11),
+    )?;
+
+    let reprice_conditions = partial_offer.reprice_conditions(&mut ctx, &new_info)?;
+    let inner_spend = Spend::new(ctx.alloc(&clvm_quote!(reprice_conditions))?, NodePtr::NIL);
+    let inner_spend = StandardLayer::new(hex_string_to_pubkey(&derivation_resp.public_key)?)
+        .delegated_inner_spend(&mut ctx, inner_spend)?;
+    let repriced = partial_offer.reprice(&mut ctx, inner_spend, new_info)?;
+
+    let mut spends = ctx.take();
+    let resp = sage.sign_coin_spends(spends.clone(), false, true).await?;
+    spends.extend(partial_offer.spend_bundle.coin_spends.clone());
+
+    let sig_from_signing = hex_string_to_signature(&resp.spend_bundle.aggregated_signature)?;
+    let sb = offer.take(SpendBundle::new(
+        spends,
+        security_sig + &sig_from_signing + &partial_offer.spend_bundle.aggregated_signature,
+    ));
+
+    // the new offer carries the reprice spends, so it can be taken right away
+    let repriced = PartialOffer {
+        spend_bundle: sb.clone(),
+        ..repriced
+    };
+    let repriced_state = PartialOfferState::Live(repriced.clone());
+    let repriced_coin = repriced.coin;
+    let repriced_str = encode_partial_offer(&repriced.to_spend_bundle(&mut ctx)?)?;
+    println!("New partial offer: {repriced_str}");
+
+    println!("Submitting transaction...");
+    let resp = client.push_tx(sb).await?;
+
+    println!("Transaction submitted; status='{}'", resp.status);
+    wait_for_coin(&client, partial_offer_coin_id, true).await?;
+    println!("Confirmed!");
+
+    PartialOfferStore::remember(
+        &mut ctx,
+        &offer_str,
+        &PartialOfferState::ClawedBack(partial_offer),
+        &lineage,
+        testnet11,
+    );
+    PartialOfferStore::remember(
+        &mut ctx,
+        &repriced_str,
+        &repriced_state,
+        &[repriced_coin],
+        testnet11,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,353 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chia::protocol::Coin;
+use chia_wallet_sdk::driver::SpendContext;
+use serde::{Deserialize, Serialize};
+use slot_machine::CliError;
+
+use crate::{PartialOfferState, current_timestamp, encode_partial_offer};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoredOfferStatus {
+    Open,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+impl StoredOfferStatus {
+    pub fn from_state(state: &PartialOfferState) -> Self {
+        match state {
+            PartialOfferState::Live(_) => Self::Open,
+            PartialOfferState::Expired(_) => Self::Expired,
+            PartialOfferState::Filled(_) => Self::Filled,
+            PartialOfferState::ClawedBack(_) => Self::Cancelled,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StoredCoin {
+    pub coin_id: String,
+    pub amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredPartialOffer {
+    pub testnet11: bool,
+    // offer string as first seen (encodes the info & original coin)
+    pub original_offer: String,
+    // offer string for the latest known coin
+    pub latest_offer: String,
+    pub maker_puzzle_hash: String,
+    // original coin first, latest known coin last
+    pub lineage: Vec<StoredCoin>,
+    pub status: StoredOfferStatus,
+    pub updated_at: u64,
+}
+
+impl StoredPartialOffer {
+    pub fn new(
+        ctx: &mut SpendContext,
+        offer: &str,
+        state: &PartialOfferState,
+        lineage: &[Coin],
+        testnet11: bool,
+    ) -> Result<Self, CliError> {
+        Ok(Self {
+            testnet11,
+            original_offer: offer.to_string(),
+            // a single-coin lineage means `offer` already describes the latest coin
+            latest_offer: if lineage.len() <= 1 {
+                offer.to_string()
+            } else {
+                encode_partial_offer(&state.offer().clone().to_spend_bundle(ctx)?)?
+            },
+            maker_puzzle_hash: hex::encode(state.offer().info.clawback_puzzle_hash),
+            lineage: lineage
+                .iter()
+                .map(|coin| StoredCoin {
+                    coin_id: hex::encode(coin.coin_id()),
+                    amount: coin.amount,
+                })
+                .collect(),
+            status: StoredOfferStatus::from_state(state),
+            updated_at: current_timestamp(),
+        })
+    }
+
+    pub fn remaining_amount(&self) -> u64 {
+        match self.status {
+            StoredOfferStatus::Open | StoredOfferStatus::Expired => {
+                self.lineage.last().map_or(0, |coin| coin.amount)
+            }
+            StoredOfferStatus::Filled | StoredOfferStatus::Cancelled => 0,
+        }
+    }
+
+    // (spent coin id, offered amount released) for every known fill
+    pub fn fills(&self) -> Vec<(String, u64)> {
+        let mut fills = self
+            .lineage
+            .windows(2)
+            // top-ups grow the coin instead
+            .filter(|coins| coins[1].amount < coins[0].amount)
+            .map(|coins| (coins[0].coin_id.clone(), coins[0].amount - coins[1].amount))
+            .collect::<Vec<_>>();
+
+        if self.status == StoredOfferStatus::Filled
+            && let Some(last_coin) = self.lineage.last()
+        {
+            fills.push((last_coin.coin_id.clone(), last_coin.amount));
+        }
+
+        fills
+    }
+
+    // Appends a newer view of the same offer (its lineage has to start
+    //  with a coin this record already knows about)
+    fn merge(&mut self, newer: StoredPartialOffer) -> bool {
+        let Some(first_coin) = newer.lineage.first() else {
+            return false;
+        };
+        let Some(index) = self
+            .lineage
+            .iter()
+            .position(|coin| coin.coin_id == first_coin.coin_id)
+        else {
+            return false;
+        };
+
+        self.lineage.truncate(index);
+        self.lineage.extend(newer.lineage);
+        self.latest_offer = newer.latest_offer;
+        self.status = newer.status;
+        self.updated_at = newer.updated_at;
+        true
+    }
+}
+
+// Known partial offers, stored as JSON lines in the user's data directory
+pub struct PartialOfferStore {
+    path: PathBuf,
+}
+
+impl PartialOfferStore {
+    pub fn open() -> Result<Self, CliError> {
+        let Some(data_dir) = dirs::data_dir() else {
+            return Err(CliError::Custom(
+                "Could not determine data directory".to_string(),
+            ));
+        };
+
+        Self::open_in(&data_dir.join("partial"))
+    }
+
+    // Store kept in `dir` instead of the user's data directory
+    pub fn open_in(dir: &Path) -> Result<Self, CliError> {
+        fs::create_dir_all(dir).map_err(|err| CliError::Custom(err.to_string()))?;
+
+        Ok(Self {
+            path: dir.join("offers.jsonl"),
+        })
+    }
+
+    pub fn load(&self) -> Result<Vec<StoredPartialOffer>, CliError> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        fs::read_to_string(&self.path)
+            .map_err(|err| CliError::Custom(err.to_string()))?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|err| {
+                    CliError::Custom(format!(
+                        "Malformed line {} in {}: {err}",
+                        index + 1,
+                        self.path.display()
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn save(&self, records: &[StoredPartialOffer]) -> Result<(), CliError> {
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp_path)
+            .map_err(|err| CliError::Custom(err.to_string()))?;
+
+        for record in records {
+            let line =
+                serde_json::to_string(record).map_err(|err| CliError::Custom(err.to_string()))?;
+            writeln!(file, "{line}").map_err(|err| CliError::Custom(err.to_string()))?;
+        }
+
+        fs::rename(&tmp_path, &self.path).map_err(|err| CliError::Custom(err.to_string()))
+    }
+
+    pub fn upsert(&self, record: StoredPartialOffer) -> Result<(), CliError> {
+        let mut records = self.load()?;
+
+        let merged = records
+            .iter_mut()
+            .filter(|existing| existing.testnet11 == record.testnet11)
+            .any(|existing| existing.merge(record.clone()));
+        if !merged {
+            records.push(record);
+        }
+
+        self.save(&records)
+    }
+
+    // Records the latest state of an offer; failures are only reported, since
+    //  the store is a convenience and should never block a command
+    pub fn remember(
+        ctx: &mut SpendContext,
+        offer: &str,
+        state: &PartialOfferState,
+        lineage: &[Coin],
+        testnet11: bool,
+    ) {
+        let res = StoredPartialOffer::new(ctx, offer, state, lineage, testnet11)
+            .and_then(|record| Self::open()?.upsert(record));
+
+        if let Err(err) = res {
+            eprintln!("Warning: could not update local offer store: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("partial-store-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn coin(id: u8, amount: u64) -> StoredCoin {
+        StoredCoin {
+            coin_id: hex::encode([id; 32]),
+            amount,
+        }
+    }
+
+    fn record(lineage: Vec<StoredCoin>, status: StoredOfferStatus) -> StoredPartialOffer {
+        StoredPartialOffer {
+            testnet11: false,
+            original_offer: "offer1original".to_string(),
+            latest_offer: format!("offer1latest{}", lineage.len()),
+            maker_puzzle_hash: hex::encode([2; 32]),
+            lineage,
+            status,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_fills_and_remaining_amount() {
+        // fill, top-up, fill
+        let open = record(
+            vec![coin(1, 1000), coin(2, 600), coin(3, 900), coin(4, 500)],
+            StoredOfferStatus::Open,
+        );
+        assert_eq!(
+            open.fills(),
+            vec![(hex::encode([1; 32]), 400), (hex::encode([3; 32]), 400)]
+        );
+        assert_eq!(open.remaining_amount(), 500);
+
+        let filled = record(vec![coin(1, 1000), coin(2, 600)], StoredOfferStatus::Filled);
+        assert_eq!(
+            filled.fills(),
+            vec![(hex::encode([1; 32]), 400), (hex::encode([2; 32]), 600)]
+        );
+        assert_eq!(filled.remaining_amount(), 0);
+
+        let cancelled = record(vec![coin(1, 1000)], StoredOfferStatus::Cancelled);
+        assert!(cancelled.fills().is_empty());
+        assert_eq!(cancelled.remaining_amount(), 0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut stored = record(vec![coin(1, 1000), coin(2, 600)], StoredOfferStatus::Open);
+
+        // unrelated lineages are left alone
+        assert!(!stored.merge(record(vec![coin(5, 100)], StoredOfferStatus::Open)));
+        assert!(!stored.merge(record(vec![], StoredOfferStatus::Open)));
+        assert_eq!(stored.lineage.len(), 2);
+
+        // newer view starting at a known coin replaces everything after it
+        assert!(stored.merge(record(
+            vec![coin(2, 600), coin(3, 200)],
+            StoredOfferStatus::Expired
+        )));
+        assert_eq!(
+            stored.lineage,
+            vec![coin(1, 1000), coin(2, 600), coin(3, 200)]
+        );
+        assert_eq!(stored.latest_offer, "offer1latest2");
+        assert_eq!(stored.status, StoredOfferStatus::Expired);
+        assert_eq!(stored.remaining_amount(), 200);
+    }
+
+    #[test]
+    fn test_upsert_and_load() -> Result<(), CliError> {
+        let dir = temp_dir("upsert");
+        let store = PartialOfferStore::open_in(&dir)?;
+        assert!(store.load()?.is_empty());
+
+        store.upsert(record(vec![coin(1, 1000)], StoredOfferStatus::Open))?;
+        store.upsert(record(
+            vec![coin(1, 1000), coin(2, 600)],
+            StoredOfferStatus::Open,
+        ))?;
+        store.upsert(record(vec![coin(5, 100)], StoredOfferStatus::Open))?;
+
+        let records = store.load()?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].lineage, vec![coin(1, 1000), coin(2, 600)]);
+        assert_eq!(records[1].lineage, vec![coin(5, 100)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_line() -> Result<(), CliError> {
+        let dir = temp_dir("malformed");
+        let store = PartialOfferStore::open_in(&dir)?;
+        store.upsert(record(vec![coin(1, 1000)], StoredOfferStatus::Open))?;
+
+        // blank lines are fine, anything else has to be a record
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join("offers.jsonl"))
+            .unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "{{\"testnet11\": false}}").unwrap();
+
+        let Err(CliError::Custom(message)) = store.load() else {
+            panic!("malformed lines should not load");
+        };
+        assert!(message.starts_with("Malformed line 3 in "));
+
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+}
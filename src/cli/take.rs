@@ -1,64 +1,275 @@
+use chia::{
+    bls::PublicKey,
+    protocol::{Bytes32, SpendBundle},
+};
+use chia_puzzle_types::Memos;
 use chia_wallet_sdk::{
     coinset::ChiaRpcClient,
     driver::{Offer, SpendContext, decode_offer},
+    types::Conditions,
+    utils::Address,
 };
 use slot_machine::{
-    CliError, SageClient, assets_xch_and_cat, assets_xch_only, get_coinset_client, parse_amount,
-    wait_for_coin,
+    CliError, SageClient, assets_xch_and_cat, assets_xch_only, get_coinset_client, get_constants,
+    get_prefix, hex_string_to_pubkey, hex_string_to_signature, parse_amount, wait_for_coin,
 };
 
-use crate::{PartialOffer, assets_cat_only, decode_partial_offer, encode_partial_offer};
+use crate::{
+    ExternalFillAuthorizer, FEE_SPLIT_BPS_DENOMINATOR, FillAuthorizer, MakerAuthority,
+    PartialOffer, PartialOfferState, PartialOfferStore, PartialOrderBook, PartialReferral,
+    PartialTaker, StandardAuthority, assets_cat_only, current_timestamp, decode_partial_offer,
+    encode_partial_offer, format_height_with_eta, format_timestamp_with_eta, next_block_height,
+};
+
+// Private offers can be filled by any of the wallet's keys (signature) or
+//  standard puzzle hashes (puzzle announcement) - keys are preferred, since
+//  announcements need an extra coin spend
+fn wallet_takers(wallet_keys: &[PublicKey]) -> Vec<PartialTaker> {
+    wallet_keys
+        .iter()
+        .map(|key| PartialTaker::Key(*key))
+        .chain(
+            wallet_keys
+                .iter()
+                .map(|key| PartialTaker::PuzzleHash(StandardAuthority::new(*key).puzzle_hash())),
+        )
+        .collect()
+}
 
 pub async fn cli_take(
-    offer: String,
+    offers: Vec<String>,
+    offers_file: Option<String>,
     take_amount_str: String,
+    referral: Option<String>,
+    authorizer_hook: Option<String>,
     fee_str: String,
     testnet11: bool,
 ) -> Result<(), CliError> {
     let fee = parse_amount(&fee_str, false)?;
+    let referral = if let Some(referral) = referral {
+        let Some((address, bps)) = referral.rsplit_once(':') else {
+            return Err(CliError::Custom(
+                "Referral needs to be given as 'address:bps'".to_string(),
+            ));
+        };
+        let bps = bps
+            .parse::<u64>()
+            .map_err(|_| CliError::Custom(format!("Invalid basis points: {bps}")))?;
+        if bps >= FEE_SPLIT_BPS_DENOMINATOR {
+            return Err(CliError::Custom(
+                "Referral fee must be less than 10000 bps".to_string(),
+            ));
+        }
+
+        Some(PartialReferral::new(
+            Address::decode(address)?.puzzle_hash,
+            bps,
+        ))
+    } else {
+        None
+    };
+    let authorizer = authorizer_hook.map(|hook| {
+        ExternalFillAuthorizer::new(hook, get_constants(testnet11).agg_sig_me_additional_data)
+    });
     let mut ctx = SpendContext::new();
 
-    let partial_offer = PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer)?)?;
+    let client = get_coinset_client(testnet11);
 
-    let mut take_amount = parse_amount(
-        &take_amount_str,
-        partial_offer.info.requested_asset_info.asset_id.is_some(),
-    )?;
-    let output_amount = PartialOffer::quote(take_amount, partial_offer.info.price_data);
-    let min_take_amount = PartialOffer::reverse_quote(output_amount, partial_offer.info.price_data);
+    let mut offers = offers;
+    if let Some(offers_file) = offers_file {
+        let contents = std::fs::read_to_string(&offers_file)
+            .map_err(|err| CliError::Custom(format!("Could not read {offers_file}: {err}")))?;
+        offers.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    let sage = SageClient::new()?;
+    // private offers are filled with one of the wallet's keys (the first 100
+    //  are checked against their allowlists)
+    let mut wallet_keys: Option<Vec<PublicKey>> = None;
+
+    let now = current_timestamp();
+    // only fetched if one of the offers has a block height start or expiration
+    let mut next_height: Option<u32> = None;
+
+    let mut order_book = PartialOrderBook::new();
+    let mut market = None;
+    for offer in offers {
+        let partial_offer =
+            PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer)?)?;
+        let (state, lineage) = partial_offer.sync_lineage(&client).await?;
+        PartialOfferStore::remember(&mut ctx, &offer, &state, &lineage, testnet11);
+
+        match state {
+            PartialOfferState::Live(partial_offer) => {
+                if partial_offer.info.fill_authorizer.is_some() && authorizer.is_none() {
+                    println!(
+                        "Skipping partial offer with coin {} (fills need to be co-signed - see --authorizer-hook)",
+                        hex::encode(partial_offer.coin.coin_id())
+                    );
+                    continue;
+                }
+
+                if let Some(start_time) = partial_offer.info.start_time
+                    && !partial_offer.info.is_started(now)
+                {
+                    println!(
+                        "Skipping partial offer with coin {} (not fillable until {})",
+                        hex::encode(partial_offer.coin.coin_id()),
+                        format_timestamp_with_eta(start_time, now)
+                    );
+                    continue;
+                }
+
+                if partial_offer.info.start_height.is_some()
+                    || partial_offer.info.expiration_height.is_some()
+                {
+                    let height = match next_height {
+                        Some(height) => height,
+                        None => *next_height.insert(next_block_height(&client).await?),
+                    };
+
+                    if let Some(start_height) = partial_offer.info.start_height
+                        && !partial_offer.info.is_started_at_height(height)
+                    {
+                        println!(
+                            "Skipping partial offer with coin {} (not fillable until height {})",
+                            hex::encode(partial_offer.coin.coin_id()),
+                            format_height_with_eta(start_height, height)
+                        );
+                        continue;
+                    }
+                    if let Some(expiration_height) = partial_offer.info.expiration_height
+                        && partial_offer.info.is_expired_at_height(height)
+                    {
+                        println!(
+                            "Skipping partial offer with coin {} (expired at height {expiration_height}; next block is {height})",
+                            hex::encode(partial_offer.coin.coin_id()),
+                        );
+                        continue;
+                    }
+                }
+
+                if partial_offer.info.taker_allowlist.is_some() {
+                    if wallet_keys.is_none() {
+                        wallet_keys = Some(
+                            sage.get_derivations(false, 0, 100)
+                                .await?
+                                .derivations
+                                .iter()
+                                .map(|derivation| hex_string_to_pubkey(&derivation.public_key))
+                                .collect::<Result<Vec<_>, CliError>>()?,
+                        );
+                    }
+
+                    if partial_offer
+                        .allowed_taker(&wallet_takers(wallet_keys.as_deref().unwrap_or_default()))
+                        .is_none()
+                    {
+                        println!(
+                            "Skipping partial offer with coin {} (wallet is not on its taker allowlist)",
+                            hex::encode(partial_offer.coin.coin_id())
+                        );
+                        continue;
+                    }
+                }
+
+                let offer_market = (
+                    partial_offer.info.offered_asset_info,
+                    partial_offer.info.requested_asset_info,
+                );
+                if market.is_some_and(|market| market != offer_market) {
+                    return Err(CliError::Custom(
+                        "All partial offers need to be for the same asset pair".to_string(),
+                    ));
+                }
 
-    if take_amount > min_take_amount {
-        println!("Saving {} mojos :)", take_amount - min_take_amount);
-        take_amount = min_take_amount;
+                market = Some(offer_market);
+                order_book.insert(partial_offer);
+            }
+            state => println!(
+                "Skipping partial offer with coin {} ({state})",
+                hex::encode(state.offer().coin.coin_id())
+            ),
+        }
     }
 
-    if partial_offer.coin.amount > output_amount {
+    let Some((offered_asset_info, requested_asset_info)) = market else {
+        return Err(CliError::Custom(
+            "No partial offer can be taken".to_string(),
+        ));
+    };
+
+    let wallet_keys = wallet_keys.unwrap_or_default();
+    let wallet_takers = wallet_takers(&wallet_keys);
+
+    let requested_amount = parse_amount(&take_amount_str, requested_asset_info.asset_id.is_some())?;
+    let plan = order_book
+        .plan_input(
+            offered_asset_info,
+            requested_asset_info,
+            requested_amount,
+            now,
+            referral,
+        )?
+        .with_takers(wallet_takers.clone());
+    if plan.is_empty() {
+        return Err(CliError::Custom(
+            "Amount is too small to fill any of the partial offers".to_string(),
+        ));
+    }
+
+    // the taker's offer covers referral fees as well
+    let take_amount = plan.total_input();
+    let output_amount = plan.output;
+    let required_fee = plan.required_fee;
+
+    if requested_amount > take_amount {
+        println!("Saving {} mojos :)", requested_amount - take_amount);
+    }
+    if plan.referral_fee > 0 {
         println!(
-            "New partial offer will be: {}",
-            encode_partial_offer(
-                &partial_offer
-                    .child(partial_offer.coin.amount - output_amount)
-                    .to_spend_bundle(&mut ctx)?
-            )?
+            "Paying {} mojos to makers and {} mojos in referral fees for {} mojos (all-in price: {})",
+            plan.input,
+            plan.referral_fee,
+            output_amount,
+            plan.effective_price()
         );
     }
 
-    let sage = SageClient::new()?;
+    for (partial_offer, quote) in &plan.fills {
+        if partial_offer.coin.amount > quote.output {
+            println!(
+                "New partial offer will be: {}",
+                encode_partial_offer(
+                    &partial_offer
+                        .child(partial_offer.coin.amount - quote.output)?
+                        .to_spend_bundle(&mut ctx)?
+                )?
+            );
+        }
+    }
+
     let offer_resp = sage
         .make_offer(
-            if let Some(offered_asset_id) = partial_offer.info.offered_asset_info.asset_id {
+            if let Some(offered_asset_id) = offered_asset_info.asset_id {
                 assets_cat_only(hex::encode(offered_asset_id), output_amount)
             } else {
                 assets_xch_only(output_amount)
             },
-            if let Some(requested_asset_id) = partial_offer.info.requested_asset_info.asset_id {
-                if let Some(required_fee) = partial_offer.info.required_fee {
+            if let Some(requested_asset_id) = requested_asset_info.asset_id {
+                if required_fee > 0 {
                     assets_xch_and_cat(required_fee, hex::encode(requested_asset_id), take_amount)
                 } else {
                     assets_cat_only(hex::encode(requested_asset_id), take_amount)
                 }
             } else {
-                assets_xch_only(take_amount + partial_offer.info.required_fee.unwrap_or(0))
+                assets_xch_only(take_amount + required_fee)
             },
             fee,
             None,
@@ -69,17 +280,125 @@ pub async fn cli_take(
 
     println!("Offer {} created.", offer_resp.offer_id);
 
-    let partial_offer_coin_id = partial_offer.coin.coin_id();
+    // states after the fill, recorded once the transaction is confirmed
+    let filled_states = plan
+        .fills
+        .iter()
+        .map(|(partial_offer, quote)| {
+            Ok(if partial_offer.coin.amount > quote.output {
+                let child = partial_offer.child(partial_offer.coin.amount - quote.output)?;
+                let lineage = vec![partial_offer.coin, child.coin];
+                (PartialOfferState::Live(child), lineage)
+            } else {
+                (
+                    PartialOfferState::Filled(partial_offer.clone()),
+                    vec![partial_offer.coin],
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
+
+    let partial_offer_coin_id = plan.fills[0].0.coin.coin_id();
+    // private fills are signed by a wallet key or announced by one of the
+    //  wallet's coins
+    let mut private_coin_ids = Vec::new();
+    let mut announced_coin_ids: Vec<(PublicKey, Vec<Bytes32>)> = Vec::new();
+    for (partial_offer, _) in &plan.fills {
+        let coin_id = partial_offer.coin.coin_id();
+        match partial_offer.allowed_taker(&wallet_takers) {
+            Some(PartialTaker::Key(_)) => private_coin_ids.push(coin_id),
+            Some(PartialTaker::PuzzleHash(puzzle_hash)) => {
+                let Some(key) = wallet_keys
+                    .iter()
+                    .find(|key| StandardAuthority::new(**key).puzzle_hash() == puzzle_hash)
+                else {
+                    continue;
+                };
+
+                if let Some((_, coin_ids)) = announced_coin_ids
+                    .iter_mut()
+                    .find(|(other, _)| other == key)
+                {
+                    coin_ids.push(coin_id);
+                } else {
+                    announced_coin_ids.push((*key, vec![coin_id]));
+                }
+            }
+            None => {}
+        }
+    }
     let offer = Offer::from_spend_bundle(&mut ctx, &decode_offer(&offer_resp.offer)?)?;
-    let sb = partial_offer.accept_offer(&mut ctx, offer)?;
+    let sb = plan.accept_offer(
+        &mut ctx,
+        offer,
+        authorizer
+            .as_ref()
+            .map(|authorizer| authorizer as &dyn FillAuthorizer),
+    )?;
+
+    // announcing coins send their full amount back to the same puzzle hash
+    for (key, coin_ids) in announced_coin_ids {
+        let authority = StandardAuthority::new(key);
+        let puzzle_hash = authority.puzzle_hash();
+        let Some(coin_record) = client
+            .get_coin_records_by_puzzle_hash(puzzle_hash, None, None, Some(false))
+            .await?
+            .coin_records
+            .unwrap_or_default()
+            .into_iter()
+            .find(|record| {
+                sb.coin_spends
+                    .iter()
+                    .all(|coin_spend| coin_spend.coin.coin_id() != record.coin.coin_id())
+            })
+        else {
+            return Err(CliError::Custom(format!(
+                "Private fills need an unspent coin at {} that isn't part of the offer",
+                Address::new(puzzle_hash, get_prefix(testnet11)).encode()?
+            )));
+        };
+
+        let conditions = coin_ids.into_iter().fold(
+            Conditions::new().create_coin(puzzle_hash, coin_record.coin.amount, Memos::None),
+            |conditions, coin_id| conditions.create_puzzle_announcement(coin_id.to_vec().into()),
+        );
+        let spend = authority.spend(&mut ctx, conditions)?;
+        ctx.spend(coin_record.coin, spend)?;
+    }
+    let announcement_spends = ctx.take();
+
+    // private fills need the wallet's signature on the partial coin spends
+    //  (or the announcing coin spends)
+    let sb = if private_coin_ids.is_empty() && announcement_spends.is_empty() {
+        sb
+    } else {
+        let private_spends = sb
+            .coin_spends
+            .iter()
+            .filter(|coin_spend| private_coin_ids.contains(&coin_spend.coin.coin_id()))
+            .cloned()
+            .chain(announcement_spends.iter().cloned())
+            .collect::<Vec<_>>();
+        let resp = sage.sign_coin_spends(private_spends, false, true).await?;
+
+        SpendBundle::new(
+            [sb.coin_spends, announcement_spends].concat(),
+            sb.aggregated_signature
+                + &hex_string_to_signature(&resp.spend_bundle.aggregated_signature)?,
+        )
+    };
 
     println!("Submitting transaction...");
-    let client = get_coinset_client(testnet11);
     let resp = client.push_tx(sb).await?;
 
     println!("Transaction submitted; status='{}'", resp.status);
     wait_for_coin(&client, partial_offer_coin_id, true).await?;
     println!("Confirmed!");
 
+    for (state, lineage) in filled_states {
+        let offer_str = encode_partial_offer(&state.offer().clone().to_spend_bundle(&mut ctx)?)?;
+        PartialOfferStore::remember(&mut ctx, &offer_str, &state, &lineage, testnet11);
+    }
+
     Ok(())
 }
@@ -0,0 +1,172 @@
+use chia::protocol::{Bytes32, Coin, SpendBundle};
+use chia_puzzle_types::{
+    Memos,
+    offer::{NotarizedPayment, Payment, SettlementPaymentsSolution},
+};
+use chia_wallet_sdk::{
+    coinset::ChiaRpcClient,
+    driver::{
+        CatSpend, Offer, Spend, SpendContext, StandardLayer, create_security_coin, decode_offer,
+        spend_security_coin,
+    },
+    types::{Conditions, puzzles::SettlementPayment},
+    utils::Address,
+};
+use clvm_traits::clvm_quote;
+use clvmr::NodePtr;
+use slot_machine::{
+    CliError, SageClient, assets_xch_and_cat, assets_xch_only, get_coinset_client, get_constants,
+    hex_string_to_pubkey, hex_string_to_signature, no_assets, parse_amount, wait_for_coin,
+};
+
+use crate::{
+    PartialOffer, PartialOfferState, PartialOfferStore, decode_partial_offer, encode_partial_offer,
+};
+
+pub async fn cli_top_up(
+    offer_str: String,
+    amount_str: String,
+    fee_str: String,
+    testnet11: bool,
+) -> Result<(), CliError> {
+    let fee = parse_amount(&fee_str, false)?;
+    let mut ctx = SpendContext::new();
+
+    let client = get_coinset_client(testnet11);
+
+    let partial_offer =
+        PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer_str)?)?;
+    let offered_asset_id = partial_offer.info.offered_asset_info.asset_id;
+    let extra_amount = parse_amount(&amount_str, offered_asset_id.is_some())?;
+
+    let (state, mut lineage) = partial_offer.sync_lineage(&client).await?;
+    let partial_offer = match state {
+        PartialOfferState::Live(partial_offer) => partial_offer,
+        state => {
+            return Err(CliError::Custom(format!(
+                "Only live partial offers can be topped up ({state})"
+            )));
+        }
+    };
+
+    let sage = SageClient::new()?;
+    let derivation_resp = &sage.get_derivations(false, 0, 1).await?.derivations[0];
+    if Address::decode(&derivation_resp.address)?.puzzle_hash
+        != partial_offer.info.clawback_puzzle_hash
+    {
+        return Err(CliError::Custom(
+            "You are not the maker of this offer".to_string(),
+        ));
+    }
+
+    let offer_resp = sage
+        .make_offer(
+            no_assets(),
+            if let Some(offered_asset_id) = offered_asset_id {
+                assets_xch_and_cat(1, hex::encode(offered_asset_id), extra_amount)
+            } else {
+                assets_xch_only(extra_amount + 1)
+            },
+            fee,
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    println!("Offer {} created.", offer_resp.offer_id);
+
+    let partial_offer_coin_id = partial_offer.coin.coin_id();
+    let offer = Offer::from_spend_bundle(&mut ctx, &decode_offer(&offer_resp.offer)?)?;
+
+    let (security_sk, security_coin) =
+        create_security_coin(&mut ctx, offer.offered_coins().xch[0])?;
+    let _ = ctx.take(); // ignore coin spend inserted by func above
+
+    // only one mojo goes to the security coin - the rest of the offered XCH
+    //  (if any) ends up in the topped-up partial coin
+    let security_coin = Coin::new(security_coin.parent_coin_info, security_coin.puzzle_hash, 1);
+
+    let offer_mod = ctx.alloc_mod::<SettlementPayment>()?;
+    for (i, given_coin) in offer.offered_coins().xch.iter().enumerate() {
+        let solution = ctx.alloc(&SettlementPaymentsSolution {
+            notarized_payments: if i == 0 {
+                vec![NotarizedPayment::new(
+                    Bytes32::default(),
+                    vec![Payment::new(
+                        security_coin.puzzle_hash,
+                        security_coin.amount,
+                        Memos::None,
+                    )],
+                )]
+            } else {
+                vec![]
+            },
+        })?;
+        ctx.spend(*given_coin, Spend::new(offer_mod, solution))?;
+    }
+
+    let mut extra_cat_spends = Vec::new();
+    if let Some(offered_asset_id) = offered_asset_id {
+        let Some(cats) = offer.offered_coins().cats.get(&offered_asset_id) else {
+            return Err(CliError::Custom(
+                "Offer does not contain the offered asset".to_string(),
+            ));
+        };
+
+        for cat in cats {
+            let solution = ctx.alloc(&SettlementPaymentsSolution::<NodePtr> {
+                notarized_payments: vec![],
+            })?;
+            extra_cat_spends.push(CatSpend::new(*cat, Spend::new(offer_mod, solution)));
+        }
+    }
+
+    let security_sig = spend_security_coin(
+        &mut ctx,
+        security_coin,
+        Conditions::new().assert_concurrent_spend(partial_offer_coin_id),
+        &security_sk,
+        get_constants(testnet11),
+    )?;
+
+    let top_up_conditions = partial_offer.top_up_conditions(&mut ctx, extra_amount)?;
+    let inner_spend = Spend::new(ctx.alloc(&clvm_quote!(top_up_conditions))?, NodePtr::NIL);
+    let inner_spend = StandardLayer::new(hex_string_to_pubkey(&derivation_resp.public_key)?)
+        .delegated_inner_spend(&mut ctx, inner_spend)?;
+    let topped_up = partial_offer.top_up(&mut ctx, inner_spend, extra_amount, extra_cat_spends)?;
+
+    let mut spends = ctx.take();
+    let resp = sage.sign_coin_spends(spends.clone(), false, true).await?;
+    spends.extend(partial_offer.spend_bundle.coin_spends);
+
+    let sig_from_signing = hex_string_to_signature(&resp.spend_bundle.aggregated_signature)?;
+    let sb = offer.take(SpendBundle::new(
+        spends,
+        security_sig + &sig_from_signing + &partial_offer.spend_bundle.aggregated_signature,
+    ));
+
+    println!("Submitting transaction...");
+    let resp = client.push_tx(sb).await?;
+
+    println!("Transaction submitted; status='{}'", resp.status);
+    wait_for_coin(&client, partial_offer_coin_id, true).await?;
+    println!("Confirmed!");
+
+    println!(
+        "Partial offer now holds {} mojos: {}",
+        topped_up.coin.amount,
+        encode_partial_offer(&topped_up.clone().to_spend_bundle(&mut ctx)?)?
+    );
+
+    lineage.push(topped_up.coin);
+    PartialOfferStore::remember(
+        &mut ctx,
+        &offer_str,
+        &PartialOfferState::Live(topped_up),
+        &lineage,
+        testnet11,
+    );
+
+    Ok(())
+}
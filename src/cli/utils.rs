@@ -1,4 +1,6 @@
+use chrono::{DateTime, SecondsFormat};
 use sage_api::{Amount, Assets, CatAmount};
+use slot_machine::CliError;
 
 pub fn assets_cat_only(asset_id: String, cat_amount: u64) -> Assets {
     Assets {
@@ -24,3 +26,164 @@ pub fn assets_cats_only(cats: Vec<(String, u64)>) -> Assets {
         nfts: vec![],
     }
 }
+
+// Chia targets 4608 blocks per day
+pub const SECONDS_PER_BLOCK_NUMERATOR: u64 = 86_400;
+pub const SECONDS_PER_BLOCK_DENOMINATOR: u64 = 4_608;
+
+// Accepts a unix timestamp, an RFC3339 date (e.g., 2030-01-01T12:00:00Z)
+//  or a duration from now (e.g., 90m, 12h, +1d12h)
+pub fn parse_timestamp(value: &str, now: u64) -> Result<u64, CliError> {
+    let value = value.trim();
+    if let Ok(timestamp) = value.parse::<u64>() {
+        return Ok(timestamp);
+    }
+
+    if let Some(duration) = parse_duration(value.strip_prefix('+').unwrap_or(value)) {
+        return now
+            .checked_add(duration)
+            .ok_or_else(|| CliError::Custom(format!("Time '{value}' is too far away")));
+    }
+
+    parse_rfc3339(value).ok_or_else(|| {
+        CliError::Custom(format!(
+            "Invalid time '{value}' (use a unix timestamp, an RFC3339 date or a duration like 2h30m)"
+        ))
+    })
+}
+
+fn parse_duration(value: &str) -> Option<u64> {
+    humantime::parse_duration(value)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+// RFC3339 date; fractions of a second are dropped
+fn parse_rfc3339(value: &str) -> Option<u64> {
+    let date = DateTime::parse_from_rfc3339(value).ok()?;
+    u64::try_from(date.timestamp()).ok()
+}
+
+// Falls back to the raw timestamp if it's not a valid date
+pub fn format_timestamp(timestamp: u64) -> String {
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+        .map_or_else(
+            || timestamp.to_string(),
+            |date| date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    if seconds < 60 {
+        return format!("{seconds}s");
+    }
+
+    let parts = [
+        (seconds / 86_400, "d"),
+        (seconds % 86_400 / 3_600, "h"),
+        (seconds % 3_600 / 60, "m"),
+    ];
+    parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// e.g., "2030-01-01T12:00:00Z (in 3h 5m)"
+pub fn format_timestamp_with_eta(timestamp: u64, now: u64) -> String {
+    let eta = if timestamp > now {
+        format!("in {}", format_duration(timestamp - now))
+    } else {
+        format!("{} ago", format_duration(now - timestamp))
+    };
+
+    format!("{} ({eta})", format_timestamp(timestamp))
+}
+
+// e.g., "1234567 (in ~2h 30m)"
+pub fn format_height_with_eta(height: u32, next_block_height: u32) -> String {
+    if height <= next_block_height {
+        return format!("{height} (reached)");
+    }
+
+    let seconds = u64::from(height - next_block_height) * SECONDS_PER_BLOCK_NUMERATOR
+        / SECONDS_PER_BLOCK_DENOMINATOR;
+    format!("{height} (in ~{})", format_duration(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90m"), Some(5_400));
+        assert_eq!(parse_duration("1d12h"), Some(129_600));
+        assert_eq!(parse_duration("2h30m15s"), Some(9_015));
+        assert_eq!(parse_duration("1w"), Some(604_800));
+        for invalid in ["", "12", "h", "1x", "1h30", "99999999999999999999s"] {
+            assert_eq!(parse_duration(invalid), None, "{invalid}");
+        }
+        assert_eq!(parse_duration("999999999999999999w"), None);
+    }
+
+    #[test]
+    fn test_parse_timestamp() -> Result<(), CliError> {
+        let now = 1_700_000_000;
+        assert_eq!(parse_timestamp("1893456000", now)?, 1_893_456_000);
+        assert_eq!(parse_timestamp("2h", now)?, now + 7_200);
+        assert_eq!(parse_timestamp(" +1d12h ", now)?, now + 129_600);
+        assert_eq!(parse_timestamp("2030-01-01T00:00:00Z", now)?, 1_893_456_000);
+        assert_eq!(
+            parse_timestamp("2030-01-01T12:30:00.5+02:30", now)?,
+            1_893_456_000 + 36_000
+        );
+        assert_eq!(
+            parse_timestamp("2029-12-31T22:00:00-02:00", now)?,
+            1_893_456_000
+        );
+        assert_eq!(parse_timestamp("2028-02-29T00:00:00Z", now)?, 1_835_395_200);
+
+        for invalid in [
+            "2030-02-29T00:00:00Z",
+            "2030-02-31T00:00:00Z",
+            "2030-04-31T00:00:00Z",
+            "2100-02-29T00:00:00Z",
+            "2030-13-01T00:00:00Z",
+            "2030-01-01T24:00:00Z",
+            "2030-01-01T00:00:00+24:00",
+            "1969-12-31T23:59:59Z",
+            "99999999999999-01-01T00:00:00Z",
+            "2030-01-01T00:00:00+99999999999999:00",
+            "2030-01-01",
+            "soon",
+        ] {
+            assert!(parse_timestamp(invalid, now).is_err(), "{invalid}");
+        }
+        assert!(parse_timestamp("18446744073709551615s", u64::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_timestamp() -> Result<(), CliError> {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_835_395_200), "2028-02-29T00:00:00Z");
+        for timestamp in [
+            0,
+            951_782_400,
+            1_700_000_000,
+            1_893_456_000 + 45_296,
+            4_102_444_799,
+            253_402_300_799,
+        ] {
+            assert_eq!(parse_timestamp(&format_timestamp(timestamp), 0)?, timestamp);
+        }
+
+        Ok(())
+    }
+}
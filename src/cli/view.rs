@@ -1,66 +1,170 @@
-use chia_wallet_sdk::{coinset::ChiaRpcClient, driver::SpendContext, utils::Address};
+use chia_wallet_sdk::{driver::SpendContext, utils::Address};
 use slot_machine::{CliError, get_coinset_client, get_prefix};
 
-use crate::{PartialOffer, decode_partial_offer};
+use crate::{
+    PartialOffer, PartialOfferState, PartialOfferStore, current_timestamp, decode_partial_offer,
+    encode_partial_offer, format_height_with_eta, format_timestamp, format_timestamp_with_eta,
+    next_block_height,
+};
 
 pub async fn cli_view(offer: String, testnet11: bool) -> Result<(), CliError> {
     let mut ctx = SpendContext::new();
+    let client = get_coinset_client(testnet11);
+
+    let original_offer = PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer)?)?;
+    let original_coin_id = original_offer.coin.coin_id();
+    let (state, lineage) = original_offer.sync_lineage(&client).await?;
+    println!("Status: {state}");
+
+    PartialOfferStore::remember(&mut ctx, &offer, &state, &lineage, testnet11);
 
-    let offer = PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer)?)?;
+    let remaining_amount = match state {
+        PartialOfferState::Live(_) | PartialOfferState::Expired(_) => state.offer().coin.amount,
+        PartialOfferState::Filled(_) | PartialOfferState::ClawedBack(_) => 0,
+    };
+    let offer = state.into_offer();
+    if remaining_amount > 0 && offer.coin.coin_id() != original_coin_id {
+        println!(
+            "Latest partial offer: {}",
+            encode_partial_offer(&offer.clone().to_spend_bundle(&mut ctx)?)?
+        );
+    }
 
+    let now = current_timestamp();
+    let remaining_requested_amount = offer
+        .reverse_quote(remaining_amount.min(offer.max_output()), now)?
+        .input;
     if let Some(requested_asset_id) = offer.info.requested_asset_info.asset_id {
         println!(
             "Remaining requested amount: {:.3} (asset id: {})",
-            PartialOffer::reverse_quote(offer.coin.amount, offer.info.price_data) as f64 / 1000.0,
+            remaining_requested_amount as f64 / 1000.0,
             hex::encode(requested_asset_id)
         );
     } else {
         println!(
             "Remaining requested amount: {:.12} XCH",
-            PartialOffer::reverse_quote(offer.coin.amount, offer.info.price_data) as f64 / 1e12,
+            remaining_requested_amount as f64 / 1e12,
         );
     }
 
     if let Some(offered_asset_id) = offer.info.offered_asset_info.asset_id {
         println!(
             "Remaining offered amount: {:.3} (asset id: {})",
-            offer.coin.amount as f64 / 1000.0,
+            remaining_amount as f64 / 1000.0,
             hex::encode(offered_asset_id)
         );
     } else {
         println!(
             "Remaining offered amount: {:.12} XCH",
-            offer.coin.amount as f64 / 1e12,
+            remaining_amount as f64 / 1e12,
         );
     }
 
-    println!("Expiration: {:?}", offer.info.expiration);
+    if let Some(expiration) = offer.info.expiration {
+        println!("Expiration: {}", format_timestamp_with_eta(expiration, now));
+    } else {
+        println!("Expiration: none");
+    }
+    if let Some(start_time) = offer.info.start_time {
+        println!(
+            "Fillable from: {}",
+            format_timestamp_with_eta(start_time, now)
+        );
+    }
+    if offer.info.start_height.is_some() || offer.info.expiration_height.is_some() {
+        let next_block_height = next_block_height(&client).await?;
+        println!("Next block height: {next_block_height}");
+        if let Some(start_height) = offer.info.start_height {
+            println!(
+                "Fillable from height: {}",
+                format_height_with_eta(start_height, next_block_height)
+            );
+        }
+        if let Some(expiration_height) = offer.info.expiration_height {
+            println!(
+                "Expiration height: {}",
+                format_height_with_eta(expiration_height, next_block_height)
+            );
+        }
+    }
+    if let Some(clawback_lock) = offer.info.clawback_lock {
+        println!(
+            "Clawback locked until: {} ({})",
+            format_timestamp_with_eta(clawback_lock, now),
+            if offer.info.is_clawback_locked(now) {
+                "locked"
+            } else {
+                "unlocked"
+            }
+        );
+    }
+    if offer.info.reclaim_args().is_some() {
+        println!("Reclaimable by anyone after expiration");
+    }
     println!("Required fee: {:?}", offer.info.required_fee);
-    println!("Pricing data: {:?}", offer.info.price_data);
-    println!(
-        "Maker address: {}",
-        Address::new(offer.info.maker_puzzle_hash, get_prefix(testnet11)).encode()?
-    );
-
-    let client = get_coinset_client(testnet11);
-
-    let mut coin_ids = offer
-        .spend_bundle
-        .coin_spends
-        .iter()
-        .map(|cs| cs.coin.coin_id())
-        .collect::<Vec<_>>();
-    coin_ids.push(offer.coin.coin_id());
-
-    let resp = client
-        .get_coin_records_by_names(coin_ids, None, None, Some(true))
-        .await?;
-
-    // Yes, this can be tricked - but works for 'normal' cancellations
+    for condition in offer.info.extra_conditions.iter() {
+        println!("Extra condition: {condition:?}");
+    }
+    println!("Pricing data: {:?}", offer.price_data_at(now));
+    if let Some(curve) = offer.info.curve {
+        println!("Bonding curve: {curve:?}");
+    }
+    if let Some(auction) = offer.info.dutch_auction {
+        println!(
+            "Dutch auction: {} steps from {} to {} (end pricing data: {:?})",
+            auction.steps,
+            format_timestamp(auction.start_time),
+            format_timestamp(auction.end_time),
+            offer.info.steps().last().map(|step| step.price_data)
+        );
+    }
     println!(
-        "Active: {:?}",
-        !resp.coin_records.unwrap().iter().any(|cr| cr.spent)
+        "Maker (clawback) address: {}",
+        Address::new(offer.info.clawback_puzzle_hash, get_prefix(testnet11)).encode()?
     );
+    if !offer.info.extra_leaves.is_empty() {
+        println!(
+            "Extra clawback leaves: {}",
+            offer
+                .info
+                .extra_leaves
+                .iter()
+                .map(hex::encode)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    if offer.info.receiver_puzzle_hash != offer.info.clawback_puzzle_hash {
+        println!(
+            "Receiver address: {}",
+            Address::new(offer.info.receiver_puzzle_hash, get_prefix(testnet11)).encode()?
+        );
+    }
+    if let Some(taker_allowlist) = &offer.info.taker_allowlist {
+        println!("Private offer - only these takers can fill it:");
+        for key in &taker_allowlist.keys {
+            println!("  Public key: {}", hex::encode(key.to_bytes()));
+        }
+        for puzzle_hash in &taker_allowlist.puzzle_hashes {
+            println!(
+                "  Address: {}",
+                Address::new(*puzzle_hash, get_prefix(testnet11)).encode()?
+            );
+        }
+    }
+    if let Some(fill_authorizer) = offer.info.fill_authorizer {
+        println!(
+            "Co-signed offer - fills need a signature from {}",
+            hex::encode(fill_authorizer.to_bytes())
+        );
+    }
+    for fee_recipient in &offer.info.fee_recipients {
+        println!(
+            "Fee recipient: {} ({} bps of each fill)",
+            Address::new(fee_recipient.puzzle_hash, get_prefix(testnet11)).encode()?,
+            fee_recipient.bps
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use chia_wallet_sdk::{coinset::ChiaRpcClient, driver::SpendContext};
+use slot_machine::{CliError, get_coinset_client};
+
+use crate::{PartialOffer, PartialOfferState, PartialOfferStore, decode_partial_offer};
+
+pub async fn cli_watch(offer: String, interval: u64, testnet11: bool) -> Result<(), CliError> {
+    let mut ctx = SpendContext::new();
+    let client = get_coinset_client(testnet11);
+
+    let mut partial_offer =
+        PartialOffer::from_spend_bundle(&mut ctx, decode_partial_offer(&offer)?)?;
+    println!(
+        "Watching partial offer coin {} ({} mojos remaining)",
+        hex::encode(partial_offer.coin.coin_id()),
+        partial_offer.coin.amount
+    );
+
+    loop {
+        let coin_id = partial_offer.coin.coin_id();
+        let record = client.get_coin_record_by_name(coin_id).await?.coin_record;
+
+        let Some(record) = record.filter(|record| record.spent) else {
+            if partial_offer.is_expired_now(&client).await? {
+                println!("Partial offer expired");
+                PartialOfferStore::remember(
+                    &mut ctx,
+                    &offer,
+                    &PartialOfferState::Expired(partial_offer.clone()),
+                    &[partial_offer.coin],
+                    testnet11,
+                );
+                return Ok(());
+            }
+
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            continue;
+        };
+
+        let Some(coin_spend) = client
+            .get_puzzle_and_solution(coin_id, Some(record.spent_block_index))
+            .await?
+            .coin_solution
+        else {
+            return Err(CliError::Custom(format!(
+                "Could not fetch spend of coin {}",
+                hex::encode(coin_id)
+            )));
+        };
+
+        let Some(spend) = partial_offer.parse_fill(&mut ctx, &coin_spend)? else {
+            // maker spends cancel the offer or resize it (top-up / partial cancel)
+            let resized = client
+                .get_coin_records_by_parent_ids(vec![coin_id], None, None, Some(true))
+                .await?
+                .coin_records
+                .unwrap_or_default()
+                .into_iter()
+                .find(|cr| cr.coin.puzzle_hash == partial_offer.coin.puzzle_hash);
+            if let Some(resized) = resized {
+                println!(
+                    "Partial offer resized by the maker at height {}: {} mojos remaining",
+                    record.spent_block_index, resized.coin.amount
+                );
+
+                let child = partial_offer.child(resized.coin.amount)?;
+                PartialOfferStore::remember(
+                    &mut ctx,
+                    &offer,
+                    &PartialOfferState::Live(child.clone()),
+                    &[partial_offer.coin, child.coin],
+                    testnet11,
+                );
+                partial_offer = child;
+                continue;
+            }
+
+            println!(
+                "Partial offer cancelled at height {}",
+                record.spent_block_index
+            );
+            PartialOfferStore::remember(
+                &mut ctx,
+                &offer,
+                &PartialOfferState::ClawedBack(partial_offer.clone()),
+                &[partial_offer.coin],
+                testnet11,
+            );
+            return Ok(());
+        };
+
+        let remaining = spend.child.as_ref().map_or(0, |child| child.coin.amount);
+        // fee split offers pay part of the other asset to the fee recipients
+        let fees = partial_offer.info.total_fees(spend.fill.other_asset_amount);
+        println!(
+            "Fill at height {}: receiver got {} mojos, fee recipients got {} mojos, taker received {} mojos, {} mojos remaining",
+            record.spent_block_index,
+            spend.fill.other_asset_amount.saturating_sub(fees),
+            fees,
+            spend.fill.offered_amount,
+            remaining
+        );
+
+        let Some(child) = spend.child else {
+            println!("Partial offer fully filled");
+            PartialOfferStore::remember(
+                &mut ctx,
+                &offer,
+                &PartialOfferState::Filled(partial_offer.clone()),
+                &[partial_offer.coin],
+                testnet11,
+            );
+            return Ok(());
+        };
+
+        PartialOfferStore::remember(
+            &mut ctx,
+            &offer,
+            &PartialOfferState::Live(child.clone()),
+            &[partial_offer.coin, child.coin],
+            testnet11,
+        );
+        partial_offer = child;
+    }
+}
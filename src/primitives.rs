@@ -1,7 +1,29 @@
 mod compress_partial;
+mod curve;
+mod fill_authorizer;
+mod ladder;
+mod maker_authority;
+mod order_book;
 mod partial;
 mod partial_info;
+mod partial_spend;
+mod quote;
+mod reclaim;
+mod referral;
+mod reprice;
+mod sync_partial;
+mod top_up;
+mod withdraw;
 
 pub use compress_partial::*;
+pub use curve::*;
+pub use fill_authorizer::*;
+pub use ladder::*;
+pub use maker_authority::*;
+pub use order_book::*;
 pub use partial::*;
 pub use partial_info::*;
+pub use partial_spend::*;
+pub use quote::*;
+pub use referral::*;
+pub use sync_partial::*;
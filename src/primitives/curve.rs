@@ -0,0 +1,251 @@
+use chia::clvm_utils::TreeHash;
+use chia_wallet_sdk::{
+    driver::{DriverError, SpendContext},
+    types::Mod,
+};
+use clvmr::{NodePtr, number::Number};
+
+use crate::{
+    ConstantProductCurveArgs, LinearCurveArgs, PartialConstantProductCurve, PartialLinearCurve,
+    PartialPriceData, Quote, QuoteError, QuoteRounding,
+};
+
+// Pricing curve of a bonding-curve partial offer - the price depends on the
+//  amount left in the coin instead of being fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialCurve {
+    Linear(PartialLinearCurve),
+    ConstantProduct(PartialConstantProductCurve),
+}
+
+impl PartialCurve {
+    pub fn linear(
+        total_amount: u64,
+        price_precision: u64,
+        start_precision: u64,
+        end_precision: u64,
+    ) -> Self {
+        Self::Linear(PartialLinearCurve {
+            total_amount,
+            price_precision,
+            start_precision,
+            end_precision,
+        })
+    }
+
+    pub fn constant_product(k: u128) -> Self {
+        Self::ConstantProduct(PartialConstantProductCurve { k })
+    }
+
+    pub fn curve_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        match self {
+            Self::Linear(curve) => ctx.curry(LinearCurveArgs {
+                total_amount: curve.total_amount,
+                price_precision: curve.price_precision,
+                start_precision: curve.start_precision,
+                end_precision: curve.end_precision,
+            }),
+            Self::ConstantProduct(curve) => ctx.curry(ConstantProductCurveArgs { k: curve.k }),
+        }
+    }
+
+    pub fn curve_puzzle_hash(&self) -> TreeHash {
+        match self {
+            Self::Linear(curve) => LinearCurveArgs {
+                total_amount: curve.total_amount,
+                price_precision: curve.price_precision,
+                start_precision: curve.start_precision,
+                end_precision: curve.end_precision,
+            }
+            .curry_tree_hash(),
+            Self::ConstantProduct(curve) => {
+                ConstantProductCurveArgs { k: curve.k }.curry_tree_hash()
+            }
+        }
+    }
+
+    // Largest amount a single fill can release from a coin holding `amount`
+    // Constant product coins can never be fully drained
+    pub fn max_output(&self, amount: u64) -> u64 {
+        match self {
+            Self::Linear(_) => amount,
+            Self::ConstantProduct(_) => amount.saturating_sub(1),
+        }
+    }
+
+    // (numerator, denominator) of the other asset amount needed to release
+    //  `output` from a coin holding `amount` - same math as the curve puzzle
+    fn cost_ratio(&self, amount: u64, output: u64) -> (Number, Number) {
+        let my_amount = Number::from(amount);
+        let new_amount = Number::from(amount - output);
+
+        match self {
+            Self::Linear(curve) => {
+                let two_total = Number::from(2) * Number::from(curve.total_amount);
+                let start_precision = Number::from(curve.start_precision);
+                let end_precision = Number::from(curve.end_precision);
+
+                (
+                    (&my_amount - &new_amount)
+                        * (&two_total * &start_precision
+                            + (end_precision - &start_precision)
+                                * (&two_total - &my_amount - &new_amount)),
+                    two_total * Number::from(curve.price_precision),
+                )
+            }
+            Self::ConstantProduct(curve) => (
+                Number::from(curve.k) * (&my_amount - &new_amount),
+                my_amount * new_amount,
+            ),
+        }
+    }
+
+    // Smallest other asset amount the curve puzzle accepts for releasing `output`
+    pub fn cost(&self, amount: u64, output: u64) -> Result<u64, QuoteError> {
+        let available = self.max_output(amount);
+        if output > available {
+            return Err(QuoteError::ExceedsAvailable { available });
+        }
+
+        let zero = Number::from(0);
+        let (numerator, denominator) = self.cost_ratio(amount, output);
+        if denominator <= zero {
+            return Err(QuoteError::InvalidPrice);
+        }
+        if numerator <= zero {
+            return Ok(0);
+        }
+
+        let cost = (numerator + &denominator - 1) / denominator;
+        u64::try_from(&cost).map_err(|_| QuoteError::Overflow)
+    }
+
+    // Largest amount released for `input` of the requested asset
+    // Cost always grows with output, so a binary search finds it
+    pub fn quote(&self, amount: u64, input: u64) -> Result<Quote, QuoteError> {
+        let mut low = 0;
+        let mut high = self.max_output(amount);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            match self.cost(amount, mid) {
+                Ok(cost) if cost <= input => low = mid,
+                Ok(_) | Err(QuoteError::Overflow) => high = mid - 1,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let min_input = self.cost(amount, low)?;
+        Ok(Quote::new(
+            min_input,
+            low,
+            input - min_input,
+            QuoteRounding::Down,
+        ))
+    }
+
+    pub fn reverse_quote(&self, amount: u64, output: u64) -> Result<Quote, QuoteError> {
+        Ok(Quote::new(
+            self.cost(amount, output)?,
+            output,
+            0,
+            QuoteRounding::Up,
+        ))
+    }
+
+    // Marginal price when the coin holds `amount` (scaled down to fit in u64s)
+    pub fn spot_price(&self, amount: u64) -> PartialPriceData {
+        let (price_precision, precision) = match self {
+            Self::Linear(curve) => {
+                let remaining = Number::from(amount);
+                let total = Number::from(curve.total_amount);
+
+                (
+                    &total * Number::from(curve.price_precision),
+                    Number::from(curve.start_precision) * &remaining
+                        + Number::from(curve.end_precision) * (total - remaining),
+                )
+            }
+            Self::ConstantProduct(curve) => (
+                Number::from(amount) * Number::from(amount),
+                Number::from(curve.k),
+            ),
+        };
+
+        let shift = price_precision
+            .bits()
+            .max(precision.bits())
+            .saturating_sub(64);
+        let fit = |value: Number| u64::try_from(&(value >> shift)).unwrap_or(0).max(1);
+
+        PartialPriceData {
+            price_precision: fit(price_precision),
+            precision: fit(precision),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clvm_traits::clvm_list;
+
+    use super::*;
+
+    #[test]
+    fn test_curve_quotes_match_puzzle() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let curves = [
+            // 1 -> 3 mojos per mojo
+            PartialCurve::linear(10_000, 1, 1, 3),
+            // 2 -> 0.5 mojos per mojo
+            PartialCurve::linear(10_000, 2, 4, 1),
+            // 10_000 offered vs. 20_000 requested
+            PartialCurve::constant_product(10_000 * 20_000),
+        ];
+
+        for curve in curves {
+            let puzzle = curve.curve_puzzle(ctx)?;
+            assert_eq!(ctx.tree_hash(puzzle), curve.curve_puzzle_hash());
+
+            let mut accepts = |amount: u64, new_amount: u64, other_asset_amount: u64| {
+                let solution = ctx.alloc(&clvm_list!(amount, new_amount, other_asset_amount))?;
+                anyhow::Ok(ctx.run(puzzle, solution)? != NodePtr::NIL)
+            };
+
+            for (amount, input) in [(10_000, 1), (10_000, 5_000), (7_777, 12_345), (123, 10)] {
+                let quote = curve.quote(amount, input)?;
+                assert!(quote.input <= input);
+                assert_eq!(quote.input + quote.unused, input);
+                assert!(quote.output <= curve.max_output(amount));
+
+                if quote.output > 0 {
+                    assert!(accepts(amount, amount - quote.output, quote.input)?);
+                }
+                if quote.input > 0 {
+                    assert!(!accepts(amount, amount - quote.output, quote.input - 1)?);
+                }
+                if quote.output < curve.max_output(amount) {
+                    assert!(!accepts(amount, amount - quote.output - 1, input)?);
+                }
+
+                let reverse_quote = curve.reverse_quote(amount, quote.output)?;
+                assert_eq!(reverse_quote.input, quote.input);
+            }
+        }
+
+        // price goes up as the coin is drained
+        let curve = PartialCurve::constant_product(10_000 * 20_000);
+        assert!(
+            curve
+                .spot_price(5_000)
+                .cmp_rate(&curve.spot_price(10_000))
+                .is_lt()
+        );
+        assert_eq!(
+            curve.reverse_quote(10_000, 10_000),
+            Err(QuoteError::ExceedsAvailable { available: 9_999 })
+        );
+
+        Ok(())
+    }
+}
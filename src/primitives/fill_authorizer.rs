@@ -0,0 +1,69 @@
+use chia::{
+    bls::{PublicKey, SecretKey, Signature, sign},
+    protocol::Bytes32,
+};
+use chia_wallet_sdk::driver::DriverError;
+
+// Fill of a co-signed partial offer, as presented to its authorizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillAuthorization {
+    pub authorizer_key: PublicKey,
+    // the partial coin being filled
+    pub coin_id: Bytes32,
+    // amount of the requested asset paid to the maker
+    pub other_asset_amount: u64,
+    // amount of the offered asset released to the taker
+    pub offered_amount: u64,
+    // the delegated puzzle creates the taker's coin and asserts the maker's
+    //  payment - its tree hash is what gets signed
+    pub delegated_puzzle_hash: Bytes32,
+}
+
+impl FillAuthorization {
+    // AGG_SIG_ME message (the additional data depends on the network)
+    pub fn message(&self, agg_sig_me_additional_data: Bytes32) -> Vec<u8> {
+        [
+            self.delegated_puzzle_hash.to_vec(),
+            self.coin_id.to_vec(),
+            agg_sig_me_additional_data.to_vec(),
+        ]
+        .concat()
+    }
+}
+
+// Whatever co-signs fills of an offer created with a fill authorizer (e.g.,
+//  an exchange's signing service that checks takers first)
+pub trait FillAuthorizer {
+    fn authorize(&self, authorization: &FillAuthorization) -> Result<Signature, DriverError>;
+}
+
+// Signs every fill with a local key - stand-in for a signing service
+#[derive(Debug, Clone)]
+pub struct LocalFillAuthorizer {
+    pub secret_key: SecretKey,
+    pub agg_sig_me_additional_data: Bytes32,
+}
+
+impl LocalFillAuthorizer {
+    pub fn new(secret_key: SecretKey, agg_sig_me_additional_data: Bytes32) -> Self {
+        Self {
+            secret_key,
+            agg_sig_me_additional_data,
+        }
+    }
+}
+
+impl FillAuthorizer for LocalFillAuthorizer {
+    fn authorize(&self, authorization: &FillAuthorization) -> Result<Signature, DriverError> {
+        if authorization.authorizer_key != self.secret_key.public_key() {
+            return Err(DriverError::Custom(
+                "Fill authorizer key does not match the partial offer".to_string(),
+            ));
+        }
+
+        Ok(sign(
+            &self.secret_key,
+            authorization.message(self.agg_sig_me_additional_data),
+        ))
+    }
+}
@@ -0,0 +1,258 @@
+use chia::protocol::{Bytes32, Coin};
+use chia_puzzle_types::{Memos, offer::Payment};
+use chia_wallet_sdk::{
+    driver::{Cat, CatSpend, DriverError, Spend, SpendContext},
+    types::Conditions,
+};
+use clvm_traits::clvm_quote;
+use clvmr::NodePtr;
+
+use crate::{PartialOffer, PartialOfferInfo, PartialPriceData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LadderSpacing {
+    Linear,
+    Geometric,
+}
+
+// Coin that creates the intermediate rung coins
+#[derive(Debug, Clone, Copy)]
+pub enum LadderFunding {
+    // id of the XCH coin
+    Xch(Bytes32),
+    Cat(Cat),
+}
+
+// Price data for each rung, lowest price first
+// Prices are in requested asset mojos per `unit` mojos of the offered asset
+pub fn ladder_price_data(
+    unit: u64,
+    min_price: u64,
+    max_price: u64,
+    rungs: u64,
+    spacing: LadderSpacing,
+) -> Vec<PartialPriceData> {
+    (0..rungs)
+        .map(|i| {
+            let price = if i == 0 || min_price == max_price {
+                min_price
+            } else if i == rungs - 1 {
+                max_price
+            } else {
+                match spacing {
+                    LadderSpacing::Linear => {
+                        min_price
+                            + ((max_price - min_price) as u128 * i as u128 / (rungs - 1) as u128)
+                                as u64
+                    }
+                    LadderSpacing::Geometric => (min_price as f64
+                        * (max_price as f64 / min_price as f64).powf(i as f64 / (rungs - 1) as f64))
+                    .round() as u64,
+                }
+            };
+
+            PartialPriceData {
+                price_precision: unit,
+                precision: price,
+            }
+        })
+        .collect()
+}
+
+// Splits `total` into `rungs` amounts; the remainder goes to the first rung
+pub fn ladder_amounts(total: u64, rungs: u64) -> Vec<u64> {
+    (0..rungs)
+        .map(|i| {
+            if i == 0 {
+                total / rungs + total % rungs
+            } else {
+                total / rungs
+            }
+        })
+        .collect()
+}
+
+impl PartialOffer {
+    // Rungs are not created directly by the funding coin - each one gets its own
+    //  (ephemeral) parent, so a single taker payment can never satisfy the
+    //  announcements of two rungs (the nonce is the parent coin id)
+    fn ladder_rung_puzzle(
+        ctx: &mut SpendContext,
+        index: usize,
+        info: &PartialOfferInfo,
+        amount: u64,
+    ) -> Result<NodePtr, DriverError> {
+        let index = ctx.alloc(&(index as u64))?;
+
+        ctx.alloc(&clvm_quote!(
+            Conditions::new()
+                .create_coin(info.inner_puzzle_hash()?.into(), amount, Memos::None)
+                .remark(index)
+        ))
+    }
+
+    // Payments the funding coin has to make for `fund_ladder`
+    pub fn ladder_funding_payments(
+        ctx: &mut SpendContext,
+        rungs: &[(PartialOfferInfo, u64)],
+    ) -> Result<Vec<Payment>, DriverError> {
+        rungs
+            .iter()
+            .enumerate()
+            .map(|(index, (info, amount))| {
+                let puzzle = Self::ladder_rung_puzzle(ctx, index, info, *amount)?;
+                Ok(Payment::new(
+                    ctx.tree_hash(puzzle).into(),
+                    *amount,
+                    Memos::None,
+                ))
+            })
+            .collect()
+    }
+
+    // Spends the intermediate coins created by the funding coin
+    // Returns one partial offer per rung; CAT rungs get their lineage proofs set
+    pub fn fund_ladder(
+        ctx: &mut SpendContext,
+        funding: LadderFunding,
+        rungs: Vec<(PartialOfferInfo, u64)>,
+    ) -> Result<Vec<Self>, DriverError> {
+        rungs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (info, amount))| {
+                let puzzle = Self::ladder_rung_puzzle(ctx, index, &info, amount)?;
+                let puzzle_hash: Bytes32 = ctx.tree_hash(puzzle).into();
+                let spend = Spend::new(puzzle, NodePtr::NIL);
+
+                match funding {
+                    LadderFunding::Xch(funding_coin_id) => {
+                        let coin = Coin::new(funding_coin_id, puzzle_hash, amount);
+                        ctx.spend(coin, spend)?;
+
+                        PartialOffer::new(coin.coin_id(), amount, info)
+                    }
+                    LadderFunding::Cat(funding_cat) => {
+                        let cat = funding_cat.child(puzzle_hash, amount);
+                        let _ = Cat::spend_all(ctx, &[CatSpend::new(cat, spend)])?;
+
+                        PartialOffer::new(
+                            cat.coin.coin_id(),
+                            amount,
+                            info.with_lineage_proof(Some(cat.child_lineage_proof())),
+                        )
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_wallet_sdk::{
+        driver::{SpendWithConditions, StandardLayer},
+        test::Simulator,
+    };
+
+    use crate::PartialOfferAssetInfo;
+
+    use super::*;
+
+    #[test]
+    fn test_ladder_prices() {
+        let prices = |spacing| {
+            ladder_price_data(1000, 100, 400, 3, spacing)
+                .into_iter()
+                .map(|price_data| price_data.precision)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(prices(LadderSpacing::Linear), vec![100, 250, 400]);
+        assert_eq!(prices(LadderSpacing::Geometric), vec![100, 200, 400]);
+        assert_eq!(ladder_amounts(1001, 4), vec![251, 250, 250, 250]);
+    }
+
+    #[test]
+    fn test_fund_ladder() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        for offered_is_cat in [false, true] {
+            let total = 90_000;
+            let maker_bls = sim.bls(total);
+
+            let (offered_asset_info, funding) = if offered_is_cat {
+                let (create_conds, cats) = Cat::issue_with_coin(
+                    ctx,
+                    maker_bls.coin.coin_id(),
+                    total,
+                    Conditions::new().create_coin(maker_bls.puzzle_hash, total, Memos::None),
+                )?;
+                StandardLayer::new(maker_bls.pk).spend(ctx, maker_bls.coin, create_conds)?;
+
+                (
+                    PartialOfferAssetInfo::cat(cats[0].info.asset_id, None),
+                    LadderFunding::Cat(cats[0]),
+                )
+            } else {
+                (
+                    PartialOfferAssetInfo::xch(),
+                    LadderFunding::Xch(maker_bls.coin.coin_id()),
+                )
+            };
+
+            // same price & amount on two rungs, which still need distinct parents
+            let rungs = ladder_price_data(1000, 100, 100, 3, LadderSpacing::Linear)
+                .into_iter()
+                .zip(ladder_amounts(total, 3))
+                .map(|(price_data, amount)| {
+                    (
+                        PartialOfferInfo::new(
+                            None,
+                            offered_asset_info,
+                            PartialOfferAssetInfo::cat(Bytes32::default(), None),
+                            maker_bls.puzzle_hash,
+                            None,
+                            None,
+                            price_data,
+                            0,
+                        ),
+                        amount,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let mut funding_conds = Conditions::new();
+            for payment in PartialOffer::ladder_funding_payments(ctx, &rungs)? {
+                funding_conds =
+                    funding_conds.create_coin(payment.puzzle_hash, payment.amount, Memos::None);
+            }
+            match funding {
+                LadderFunding::Xch(_) => {
+                    StandardLayer::new(maker_bls.pk).spend(ctx, maker_bls.coin, funding_conds)?;
+                }
+                LadderFunding::Cat(funding_cat) => {
+                    let inner_spend = StandardLayer::new(maker_bls.pk)
+                        .spend_with_conditions(ctx, funding_conds)?;
+                    let _ = Cat::spend_all(ctx, &[CatSpend::new(funding_cat, inner_spend)])?;
+                }
+            }
+
+            let offers = PartialOffer::fund_ladder(ctx, funding, rungs)?;
+            sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+            assert_eq!(offers.len(), 3);
+            assert_ne!(
+                offers[1].coin.parent_coin_info,
+                offers[2].coin.parent_coin_info
+            );
+            for offer in offers {
+                assert!(sim.coin_state(offer.coin.coin_id()).is_some());
+                assert_eq!(offer.info.lineage_proof.is_some(), offered_is_cat);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+use chia::{bls::PublicKey, clvm_utils::TreeHash, protocol::Bytes32};
+use chia_puzzle_types::standard::StandardArgs;
+use chia_wallet_sdk::{
+    driver::{DriverError, Spend, SpendContext, StandardLayer},
+    types::{Conditions, Mod},
+};
+use clvm_traits::clvm_quote;
+use clvmr::NodePtr;
+
+use crate::{ASSERT_SECONDS_RELATIVE_OPCODE, TimelockArgs, TimelockSolution};
+
+// Whatever controls the clawback leaf of a partial offer
+// Used to build the inner spend passed to `claw_back` (and friends) for
+//  makers that don't use the standard puzzle (e.g., vaults or multisigs)
+pub trait MakerAuthority {
+    // Clawback puzzle hash (leaf of the 1-of-n tree)
+    fn puzzle_hash(&self) -> Bytes32;
+
+    // Spend of the clawback puzzle that outputs exactly `conditions`
+    // Signatures are collected separately
+    fn spend(&self, ctx: &mut SpendContext, conditions: Conditions) -> Result<Spend, DriverError>;
+}
+
+// Standard puzzle - conditions are delegated, so the key signs them
+#[derive(Debug, Clone, Copy)]
+pub struct StandardAuthority {
+    pub public_key: PublicKey,
+}
+
+impl StandardAuthority {
+    pub fn new(public_key: PublicKey) -> Self {
+        Self { public_key }
+    }
+}
+
+impl MakerAuthority for StandardAuthority {
+    fn puzzle_hash(&self) -> Bytes32 {
+        StandardArgs::curry_tree_hash(self.public_key).into()
+    }
+
+    fn spend(&self, ctx: &mut SpendContext, conditions: Conditions) -> Result<Spend, DriverError> {
+        let delegated_puzzle = ctx.alloc(&clvm_quote!(conditions))?;
+        StandardLayer::new(self.public_key)
+            .delegated_inner_spend(ctx, Spend::new(delegated_puzzle, NodePtr::NIL))
+    }
+}
+
+// Wraps another authority in the timelock puzzle - used for time-locked
+//  recovery leaves
+#[derive(Debug, Clone, Copy)]
+pub struct TimelockAuthority<A> {
+    pub condition_opcode: u8,
+    pub seconds: u64,
+    pub inner: A,
+}
+
+impl<A> TimelockAuthority<A> {
+    // Matches `PartialOfferInfo::recovery_leaf`
+    pub fn recovery(delay: u64, inner: A) -> Self {
+        Self {
+            condition_opcode: ASSERT_SECONDS_RELATIVE_OPCODE,
+            seconds: delay,
+            inner,
+        }
+    }
+}
+
+impl<A: MakerAuthority> MakerAuthority for TimelockAuthority<A> {
+    fn puzzle_hash(&self) -> Bytes32 {
+        TimelockArgs {
+            condition_opcode: self.condition_opcode,
+            seconds: self.seconds,
+            inner_puzzle: TreeHash::from(self.inner.puzzle_hash()),
+        }
+        .curry_tree_hash()
+        .into()
+    }
+
+    fn spend(&self, ctx: &mut SpendContext, conditions: Conditions) -> Result<Spend, DriverError> {
+        let inner_spend = self.inner.spend(ctx, conditions)?;
+        let puzzle = ctx.curry(TimelockArgs {
+            condition_opcode: self.condition_opcode,
+            seconds: self.seconds,
+            inner_puzzle: inner_spend.puzzle,
+        })?;
+        let solution = ctx.alloc(&TimelockSolution {
+            inner_solution: inner_spend.solution,
+        })?;
+
+        Ok(Spend::new(puzzle, solution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia::{clvm_utils::tree_hash_atom, protocol::Coin};
+    use chia_wallet_sdk::test::Simulator;
+
+    use crate::{PartialOffer, PartialOfferAssetInfo, PartialOfferInfo, PartialPriceData};
+
+    use super::*;
+
+    // clawback puzzle that isn't a standard one - `1` returns its solution,
+    //  so anyone can spend it
+    struct AnyoneCanSpend;
+
+    impl MakerAuthority for AnyoneCanSpend {
+        fn puzzle_hash(&self) -> Bytes32 {
+            tree_hash_atom(&[1]).into()
+        }
+
+        fn spend(
+            &self,
+            ctx: &mut SpendContext,
+            conditions: Conditions,
+        ) -> Result<Spend, DriverError> {
+            let puzzle = ctx.alloc(&1)?;
+            let solution = ctx.alloc(&conditions)?;
+            Ok(Spend::new(puzzle, solution))
+        }
+    }
+
+    #[test]
+    fn test_custom_maker_authority() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let authority = AnyoneCanSpend;
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            authority.puzzle_hash(),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        );
+        let coin = sim.new_coin(info.puzzle_hash()?, 1_000);
+        let partial_offer = PartialOffer::new(coin.parent_coin_info, 1_000, info)?;
+
+        let conditions = partial_offer.withdraw_conditions(ctx, 1_000)?;
+        let inner_spend = authority.spend(ctx, conditions)?;
+        partial_offer.claw_back(ctx, inner_spend)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        let returned = Coin::new(partial_offer.coin.coin_id(), authority.puzzle_hash(), 1_000);
+        assert!(sim.coin_state(returned.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recovery_leaf() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let operator_bls = sim.bls(0);
+        let operator = StandardAuthority::new(operator_bls.pk);
+        let recovery = TimelockAuthority::recovery(3_600, AnyoneCanSpend);
+
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            operator.puzzle_hash(),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        )
+        .with_extra_leaves(vec![PartialOfferInfo::recovery_leaf(
+            3_600,
+            AnyoneCanSpend.puzzle_hash(),
+        )]);
+        assert_eq!(recovery.puzzle_hash(), info.extra_leaves[0]);
+        assert_eq!(info.leaves()?.len(), 3);
+
+        let coin = sim.new_coin(info.puzzle_hash()?, 1_000);
+        let partial_offer = PartialOffer::new(coin.parent_coin_info, 1_000, info)?;
+
+        // unknown leaves are rejected
+        let conditions = partial_offer.withdraw_conditions(ctx, 1_000)?;
+        let inner_spend = AnyoneCanSpend.spend(ctx, conditions)?;
+        assert!(partial_offer.claw_back(ctx, inner_spend).is_err());
+
+        // recovery leaf only works once the coin is old enough
+        let conditions = partial_offer.withdraw_conditions(ctx, 1_000)?;
+        let inner_spend = recovery.spend(ctx, conditions)?;
+        partial_offer.claw_back(ctx, inner_spend)?;
+        assert!(sim.spend_coins(ctx.take(), &[]).is_err());
+
+        sim.pass_time(3_600);
+        let conditions = partial_offer.withdraw_conditions(ctx, 1_000)?;
+        let inner_spend = recovery.spend(ctx, conditions)?;
+        partial_offer.claw_back(ctx, inner_spend)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clawback_lock() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let clawback_lock = 10_000_000_000;
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            AnyoneCanSpend.puzzle_hash(),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        )
+        .with_clawback_lock(Some(clawback_lock));
+        assert!(!info.leaves()?.contains(&AnyoneCanSpend.puzzle_hash()));
+        assert!(info.is_clawback_locked(clawback_lock - 1));
+        assert!(!info.is_clawback_locked(clawback_lock));
+
+        let hint = ctx.alloc(&info.to_hint())?;
+        let parsed = PartialOfferInfo::from_hint(&ctx.extract(hint)?).unwrap();
+        assert_eq!(parsed.clawback_lock, Some(clawback_lock));
+        assert_eq!(parsed.puzzle_hash()?, info.puzzle_hash()?);
+
+        let coin = sim.new_coin(info.puzzle_hash()?, 1_000);
+        let partial_offer = PartialOffer::new(coin.parent_coin_info, 1_000, info)?;
+
+        // the maker can't pull liquidity before the lock expires
+        let conditions = partial_offer.withdraw_conditions(ctx, 1_000)?;
+        let inner_spend = AnyoneCanSpend.spend(ctx, conditions)?;
+        partial_offer.claw_back(ctx, inner_spend)?;
+        assert!(sim.spend_coins(ctx.take(), &[]).is_err());
+
+        sim.pass_time(clawback_lock);
+        let conditions = partial_offer.withdraw_conditions(ctx, 1_000)?;
+        let inner_spend = AnyoneCanSpend.spend(ctx, conditions)?;
+        partial_offer.claw_back(ctx, inner_spend)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use chia::protocol::{Bytes32, SpendBundle};
+use chia_wallet_sdk::driver::{DriverError, Offer, SpendContext};
+
+use crate::{
+    FillAuthorizer, PartialOffer, PartialOfferAssetInfo, PartialReferral, PartialTaker, Quote,
+    QuoteError,
+};
+
+// (offered asset, requested asset)
+pub type PartialMarket = (PartialOfferAssetInfo, PartialOfferAssetInfo);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PartialPriceLevel {
+    pub coin_id: Bytes32,
+    // requested asset mojos per offered asset mojo
+    pub price: f64,
+    pub offered_amount: u64,
+    pub requested_amount: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionPlan {
+    pub fills: Vec<(PartialOffer, Quote)>,
+    // requested asset given to the makers (excl. required and referral fees)
+    pub input: u64,
+    // offered asset received by the taker
+    pub output: u64,
+    // sum of all required fees (in XCH)
+    pub required_fee: u64,
+    // sum of all referral fees (in the requested asset)
+    pub referral_fee: u64,
+    pub referral: Option<PartialReferral>,
+    // identities used to fill private offers
+    pub takers: Vec<PartialTaker>,
+    // time the offers were priced at
+    pub now: u64,
+}
+
+impl ExecutionPlan {
+    pub fn new(
+        fills: Vec<(PartialOffer, Quote)>,
+        now: u64,
+        referral: Option<PartialReferral>,
+    ) -> Self {
+        Self {
+            input: fills.iter().map(|(_, quote)| quote.input).sum(),
+            output: fills.iter().map(|(_, quote)| quote.output).sum(),
+            required_fee: fills
+                .iter()
+                .map(|(offer, _)| offer.info.required_fee.unwrap_or(0))
+                .sum(),
+            referral_fee: fills.iter().map(|(_, quote)| quote.referral_fee).sum(),
+            referral,
+            takers: Vec::new(),
+            fills,
+            now,
+        }
+    }
+
+    pub fn with_takers(self, takers: Vec<PartialTaker>) -> Self {
+        Self { takers, ..self }
+    }
+
+    // Requested asset the taker's offer has to give (excl. required fees)
+    pub fn total_input(&self) -> u64 {
+        self.input + self.referral_fee
+    }
+
+    // Offered asset received per requested asset given, referral fees included
+    pub fn effective_price(&self) -> f64 {
+        if self.total_input() == 0 {
+            0.0
+        } else {
+            self.output as f64 / self.total_input() as f64
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fills.is_empty()
+    }
+
+    // `authorizer` co-signs fills of offers that require it
+    pub fn accept_offer(
+        self,
+        ctx: &mut SpendContext,
+        offer: Offer,
+        authorizer: Option<&dyn FillAuthorizer>,
+    ) -> Result<SpendBundle, DriverError> {
+        PartialOffer::accept_offers(
+            ctx,
+            self.fills
+                .into_iter()
+                .map(|(partial_offer, quote)| (partial_offer, quote.input))
+                .collect(),
+            offer,
+            self.now,
+            self.referral,
+            &self.takers,
+            authorizer,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PartialOrderBook {
+    offers: HashMap<PartialMarket, Vec<PartialOffer>>,
+}
+
+impl PartialOrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds an offer to its market, replacing any offer with the same coin
+    pub fn insert(&mut self, offer: PartialOffer) {
+        let offers = self
+            .offers
+            .entry((
+                offer.info.offered_asset_info,
+                offer.info.requested_asset_info,
+            ))
+            .or_default();
+
+        offers.retain(|existing| existing.coin.coin_id() != offer.coin.coin_id());
+        offers.push(offer);
+    }
+
+    pub fn markets(&self) -> impl Iterator<Item = &PartialMarket> {
+        self.offers.keys()
+    }
+
+    // Offers that can currently be filled, best price (for the taker) first
+    // Height-based start and expiration aren't checked here
+    pub fn offers(
+        &self,
+        offered_asset_info: PartialOfferAssetInfo,
+        requested_asset_info: PartialOfferAssetInfo,
+        now: u64,
+    ) -> Vec<PartialOffer> {
+        let mut offers = self
+            .offers
+            .get(&(offered_asset_info, requested_asset_info))
+            .map(|offers| {
+                offers
+                    .iter()
+                    .filter(|offer| {
+                        offer.coin.amount > 0
+                            && !offer.is_expired(now)
+                            && offer.info.is_started(now)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        offers.sort_by(|a, b| b.price_data_at(now).cmp_rate(&a.price_data_at(now)));
+        offers
+    }
+
+    pub fn ladder(
+        &self,
+        offered_asset_info: PartialOfferAssetInfo,
+        requested_asset_info: PartialOfferAssetInfo,
+        now: u64,
+    ) -> Result<Vec<PartialPriceLevel>, QuoteError> {
+        self.offers(offered_asset_info, requested_asset_info, now)
+            .into_iter()
+            .map(|offer| {
+                let price_data = offer.price_data_at(now);
+                Ok(PartialPriceLevel {
+                    coin_id: offer.coin.coin_id(),
+                    price: price_data.precision as f64 / price_data.price_precision as f64,
+                    offered_amount: offer.max_output(),
+                    requested_amount: offer.reverse_quote(offer.max_output(), now)?.input,
+                })
+            })
+            .collect()
+    }
+
+    // Bids buy `base` with `quote`, asks sell `base` for `quote`
+    // Prices are expressed in `quote` mojos per `base` mojo on both sides
+    pub fn bids_and_asks(
+        &self,
+        base: PartialOfferAssetInfo,
+        quote: PartialOfferAssetInfo,
+        now: u64,
+    ) -> Result<(Vec<PartialPriceLevel>, Vec<PartialPriceLevel>), QuoteError> {
+        let bids = self
+            .ladder(quote, base, now)?
+            .into_iter()
+            .map(|level| PartialPriceLevel {
+                price: 1.0 / level.price,
+                ..level
+            })
+            .collect();
+
+        Ok((bids, self.ladder(base, quote, now)?))
+    }
+
+    // How much offered asset `input` of the requested asset buys
+    // Referral fees, if any, are paid out of `input`
+    pub fn plan_input(
+        &self,
+        offered_asset_info: PartialOfferAssetInfo,
+        requested_asset_info: PartialOfferAssetInfo,
+        input: u64,
+        now: u64,
+        referral: Option<PartialReferral>,
+    ) -> Result<ExecutionPlan, QuoteError> {
+        Ok(ExecutionPlan::new(
+            PartialOffer::sweep(
+                self.offers(offered_asset_info, requested_asset_info, now),
+                input,
+                now,
+                referral.as_ref(),
+            )?,
+            now,
+            referral,
+        ))
+    }
+
+    // How much requested asset is needed to receive `output` of the offered asset
+    // The plan may slightly exceed `output` due to rounding
+    // Referral fees, if any, are paid on top of the required input
+    pub fn plan_output(
+        &self,
+        offered_asset_info: PartialOfferAssetInfo,
+        requested_asset_info: PartialOfferAssetInfo,
+        output: u64,
+        now: u64,
+        referral: Option<PartialReferral>,
+    ) -> Result<ExecutionPlan, QuoteError> {
+        let mut remaining = output;
+        let mut fills = Vec::new();
+        for offer in self.offers(offered_asset_info, requested_asset_info, now) {
+            if remaining == 0 {
+                break;
+            }
+
+            let input = offer
+                .reverse_quote(remaining.min(offer.max_output()), now)?
+                .input;
+            let quote = match offer.quote(input, now) {
+                Ok(quote) => quote.with_referral(referral.as_ref()),
+                Err(QuoteError::BelowMinimum { .. }) => continue,
+                Err(err) => return Err(err),
+            };
+            if quote.output == 0 {
+                continue;
+            }
+
+            remaining = remaining.saturating_sub(quote.output);
+            fills.push((offer, quote));
+        }
+
+        Ok(ExecutionPlan::new(fills, now, referral))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives::partial::tests::offer_with_price;
+
+    use super::*;
+
+    #[test]
+    fn test_order_book_plans() -> anyhow::Result<()> {
+        let xch = PartialOfferAssetInfo::xch();
+        let cat = PartialOfferAssetInfo::cat(Bytes32::default(), None);
+
+        let mut book = PartialOrderBook::new();
+        book.insert(offer_with_price(Bytes32::new([1; 32]), 1000, 200, None)?);
+        book.insert(offer_with_price(
+            Bytes32::new([2; 32]),
+            1000,
+            100,
+            Some(50),
+        )?);
+        book.insert(offer_with_price(Bytes32::new([3; 32]), 1000, 100, None)?);
+
+        let (bids, asks) = book.bids_and_asks(xch, cat, 100)?;
+        assert!(bids.is_empty());
+        assert_eq!(asks.len(), 2);
+        assert_eq!(asks[0].price, 0.1);
+        assert_eq!(asks[0].requested_amount, 100);
+        assert_eq!(asks[1].price, 0.2);
+
+        // expired offer is ignored
+        let plan = book.plan_output(xch, cat, 1500, 100, None)?;
+        assert_eq!(plan.fills.len(), 2);
+        assert_eq!((plan.input, plan.output), (200, 1500));
+
+        // 5 mojos would be below the minimum of the second offer
+        let plan = book.plan_input(xch, cat, 105, 100, None)?;
+        assert_eq!(plan.fills.len(), 1);
+        assert_eq!((plan.input, plan.output), (100, 1000));
+
+        // before expiration, both cheap offers are used
+        let plan = book.plan_input(xch, cat, 200, 10, None)?;
+        assert_eq!((plan.input, plan.output), (200, 2000));
+
+        // referral fees come out of the taker's input
+        let referral = PartialReferral::new(Bytes32::new([4; 32]), 100);
+        let plan = book.plan_input(xch, cat, 202, 10, Some(referral))?;
+        assert_eq!((plan.input, plan.output), (200, 2000));
+        assert_eq!(plan.referral_fee, 2);
+        assert_eq!(plan.total_input(), 202);
+        assert!(plan.effective_price() < 10.0);
+
+        let plan = book.plan_output(xch, cat, 1000, 100, Some(referral))?;
+        assert_eq!((plan.input, plan.referral_fee), (100, 1));
+        assert_eq!(plan.fills[0].1.total_input(), 101);
+
+        Ok(())
+    }
+}
@@ -12,17 +12,21 @@ use chia_wallet_sdk::{
     driver::{Cat, CatInfo, CatSpend, DriverError, Offer, Spend, SpendContext},
     prelude::{Coin, CreateCoin},
     types::{
-        MerkleTree, Mod,
+        Mod, announcement_id,
         puzzles::{
             P2OneOfManyArgs, P2OneOfManySolution, RevocationArgs, RevocationSolution,
             SettlementPayment,
         },
     },
 };
-use clvm_traits::{ToClvm, clvm_tuple};
+use clvm_traits::{ToClvm, clvm_list, clvm_quote, clvm_tuple};
 use clvmr::{Allocator, NodePtr};
 
-use crate::{PartialOfferInfo, PartialPriceData, PartialSolution};
+use crate::{
+    ASSERT_SECONDS_ABSOLUTE_OPCODE, FillAuthorization, FillAuthorizer, FillAuthorizerSolution,
+    PartialCurveSolution, PartialOfferInfo, PartialPriceData, PartialReferral, PartialSolution,
+    PartialTaker, Quote, QuoteError, TimelockArgs, TimelockSolution,
+};
 
 #[derive(Debug, Clone)]
 pub struct PartialOffer {
@@ -34,12 +38,16 @@ pub struct PartialOffer {
 }
 
 impl PartialOffer {
-    pub fn new(parent_coin_id: Bytes32, amount: u64, info: PartialOfferInfo) -> Self {
-        Self {
-            coin: Coin::new(parent_coin_id, info.puzzle_hash(), amount),
+    pub fn new(
+        parent_coin_id: Bytes32,
+        amount: u64,
+        info: PartialOfferInfo,
+    ) -> Result<Self, DriverError> {
+        Ok(Self {
+            coin: Coin::new(parent_coin_id, info.puzzle_hash()?, amount),
             info,
             spend_bundle: SpendBundle::new(Vec::new(), Signature::default()),
-        }
+        })
     }
 
     pub fn take(self, spend_bundle: SpendBundle) -> SpendBundle {
@@ -49,6 +57,26 @@ impl PartialOffer {
         )
     }
 
+    // Like `take`, but leaves out coin spends `spend_bundle` already has
+    // Offers from the same ladder share their creation spends (and signature),
+    //  which can only be included once
+    pub fn take_shared(self, spend_bundle: SpendBundle) -> SpendBundle {
+        let coin_spends = self
+            .spend_bundle
+            .coin_spends
+            .into_iter()
+            .filter(|coin_spend| !spend_bundle.coin_spends.contains(coin_spend))
+            .collect::<Vec<_>>();
+        if coin_spends.is_empty() {
+            return spend_bundle;
+        }
+
+        SpendBundle::new(
+            [coin_spends, spend_bundle.coin_spends].concat(),
+            self.spend_bundle.aggregated_signature + &spend_bundle.aggregated_signature,
+        )
+    }
+
     pub fn from_spend_bundle(
         ctx: &mut SpendContext,
         spend_bundle: SpendBundle,
@@ -84,7 +112,7 @@ impl PartialOffer {
 
         let partial_coin = Coin::new(
             special_coin_spend.coin.parent_coin_info,
-            info.puzzle_hash(),
+            info.puzzle_hash()?,
             special_coin_spend.coin.amount,
         );
 
@@ -111,38 +139,116 @@ impl PartialOffer {
         Ok(self.spend_bundle)
     }
 
+    // Spends the partial puzzle step that's active at `now`
+    // Private offers also need an allowlisted `taker`, which has to sign (or
+    //  announce) the partial coin id in the same spend bundle
+    // Co-signed offers create the taker's coin through the authorizer's
+    //  delegated puzzle instead - see `fill_authorization` for the signature
     pub fn partial_coin_spend<T>(
         &self,
         ctx: &mut SpendContext,
         other_asset_amount: u64,
         create_coin: Option<CreateCoin<T>>,
-    ) -> Result<(Spend, NotarizedPayment), DriverError>
+        now: u64,
+        taker: Option<PartialTaker>,
+    ) -> Result<(Spend, Vec<NotarizedPayment>), DriverError>
     where
         T: ToClvm<Allocator>,
     {
-        let args = self.info.to_args(ctx)?;
-        let partial_puzzle = ctx.curry(&args)?;
+        let Some(step) = self.info.active_step(now) else {
+            return Err(DriverError::Custom(
+                "Partial offer can't be filled at this time".to_string(),
+            ));
+        };
+        let inner_puzzle_solution = match (&self.info.taker_allowlist, taker) {
+            (None, _) => NodePtr::NIL,
+            (Some(taker_allowlist), Some(taker)) if taker_allowlist.allows(&taker) => {
+                ctx.alloc(&taker.solution(self.coin.coin_id(), ()))?
+            }
+            (Some(_), _) => {
+                return Err(DriverError::Custom(
+                    "Taker is not allowed to fill this partial offer".to_string(),
+                ));
+            }
+        };
+        let (inner_puzzle_solution, create_coin) = if self.info.fill_authorizer.is_some() {
+            let Some(create_coin) = create_coin else {
+                return Err(DriverError::Custom(
+                    "Co-signed fills have to create the taker's coin".to_string(),
+                ));
+            };
 
-        let partial_ph = self.info.partial_puzzle_hash().into();
-        let merkle_tree = MerkleTree::new(&[partial_ph, self.info.maker_puzzle_hash]);
+            let delegated_puzzle =
+                self.fill_delegated_puzzle(ctx, other_asset_amount, &create_coin)?;
+            (
+                ctx.alloc(&FillAuthorizerSolution {
+                    delegated_puzzle,
+                    delegated_solution: NodePtr::NIL,
+                    inner_solution: inner_puzzle_solution,
+                })?,
+                None,
+            )
+        } else {
+            (inner_puzzle_solution, create_coin)
+        };
+        let my_data = CoinProof {
+            parent_coin_info: self.coin.parent_coin_info,
+            inner_puzzle_hash: self.info.inner_puzzle_hash()?.into(),
+            amount: self.coin.amount,
+        };
+        let create_coin_rest =
+            create_coin.map(|cc| clvm_tuple!(cc.puzzle_hash, clvm_tuple!(cc.amount, cc.memos)));
+        let (partial_puzzle, partial_solution) = if let Some(curve) = self.info.curve {
+            // the taker gets whatever the curve releases for `other_asset_amount`
+            let output = curve.quote(self.coin.amount, other_asset_amount)?.output;
+
+            let args = self.info.curve_args(ctx, &step, &curve)?;
+            (
+                ctx.curry(&args)?,
+                ctx.alloc(&PartialCurveSolution {
+                    my_data,
+                    other_asset_amount,
+                    new_amount: self.coin.amount - output,
+                    create_coin_rest,
+                    cat_maker_solution: (),
+                    inner_puzzle_solution,
+                })?,
+            )
+        } else if !self.info.fee_recipients.is_empty() {
+            let args = self.info.fee_split_args(ctx, &step)?;
+            (
+                ctx.curry(&args)?,
+                ctx.alloc(&PartialSolution {
+                    my_data,
+                    other_asset_amount,
+                    create_coin_rest,
+                    cat_maker_solution: (),
+                    inner_puzzle_solution,
+                })?,
+            )
+        } else {
+            let args = self.info.step_args(ctx, &step)?;
+            (
+                ctx.curry(&args)?,
+                ctx.alloc(&PartialSolution {
+                    my_data,
+                    other_asset_amount,
+                    create_coin_rest,
+                    cat_maker_solution: (),
+                    inner_puzzle_solution,
+                })?,
+            )
+        };
+
+        let partial_ph = self.info.step_puzzle_hash(&step)?.into();
+        let merkle_tree = self.info.merkle_tree()?;
         let inner_puzzle = ctx.curry(P2OneOfManyArgs::new(merkle_tree.root()))?;
         let inner_solution = P2OneOfManySolution {
             merkle_proof: merkle_tree
                 .proof(partial_ph)
                 .ok_or(DriverError::InvalidMerkleProof)?,
             puzzle: partial_puzzle,
-            solution: PartialSolution {
-                my_data: CoinProof {
-                    parent_coin_info: self.coin.parent_coin_info,
-                    inner_puzzle_hash: self.info.inner_puzzle_hash().into(),
-                    amount: self.coin.amount,
-                },
-                other_asset_amount,
-                create_coin_rest: create_coin
-                    .map(|cc| clvm_tuple!(cc.puzzle_hash, clvm_tuple!(cc.amount, cc.memos))),
-                cat_maker_solution: (),
-                inner_puzzle_solution: (),
-            },
+            solution: partial_solution,
         };
         let inner_solution = ctx.alloc(&inner_solution)?;
 
@@ -171,12 +277,12 @@ impl PartialOffer {
                     {
                         RevocationArgs::new(
                             hidden_puzzle_hash,
-                            self.info.inner_puzzle_hash().into(),
+                            self.info.inner_puzzle_hash()?.into(),
                         )
                         .curry_tree_hash()
                         .into()
                     } else {
-                        self.info.inner_puzzle_hash().into()
+                        self.info.inner_puzzle_hash()?.into()
                     },
                     amount: self.coin.amount,
                 },
@@ -189,22 +295,146 @@ impl PartialOffer {
 
         Ok((
             Spend::new(puzzle, solution),
-            self.notatized_payment(ctx, other_asset_amount)?,
+            self.notarized_payments(ctx, other_asset_amount)?,
         ))
     }
 
-    pub fn claw_back(&self, ctx: &mut SpendContext, inner_spend: Spend) -> Result<(), DriverError> {
-        let partial_ph = self.info.partial_puzzle_hash().into();
-        let merkle_tree = MerkleTree::new(&[partial_ph, self.info.maker_puzzle_hash]);
+    // Delegated puzzle signed by the authorizer of a co-signed offer - creates
+    //  the taker's coin and asserts the maker's payment, so the signature
+    //  can't be reused for a different fill
+    fn fill_delegated_puzzle<T>(
+        &self,
+        ctx: &mut SpendContext,
+        other_asset_amount: u64,
+        create_coin: &CreateCoin<T>,
+    ) -> Result<NodePtr, DriverError>
+    where
+        T: ToClvm<Allocator>,
+    {
+        let payment = self.notatized_payment(ctx, other_asset_amount)?;
+        let payment = ctx.alloc(&payment)?;
+        let payment_announcement = announcement_id(
+            PartialOfferInfo::full_asset_puzzle_hash(
+                self.info.requested_asset_info,
+                SETTLEMENT_PAYMENT_HASH.into(),
+            ),
+            ctx.tree_hash(payment).to_vec(),
+        );
+
+        ctx.alloc(&clvm_quote!(clvm_list!(
+            clvm_tuple!(
+                51u8, // CREATE_COIN
+                clvm_tuple!(
+                    create_coin.puzzle_hash,
+                    clvm_tuple!(create_coin.amount, &create_coin.memos)
+                )
+            ),
+            clvm_list!(63u8, payment_announcement) // ASSERT_PUZZLE_ANNOUNCEMENT
+        )))
+    }
+
+    // What the authorizer of a co-signed offer has to sign for a fill
+    //  (None for offers without a fill authorizer)
+    pub fn fill_authorization<T>(
+        &self,
+        ctx: &mut SpendContext,
+        other_asset_amount: u64,
+        create_coin: &CreateCoin<T>,
+    ) -> Result<Option<FillAuthorization>, DriverError>
+    where
+        T: ToClvm<Allocator>,
+    {
+        let Some(authorizer_key) = self.info.fill_authorizer else {
+            return Ok(None);
+        };
+
+        let delegated_puzzle = self.fill_delegated_puzzle(ctx, other_asset_amount, create_coin)?;
+        Ok(Some(FillAuthorization {
+            authorizer_key,
+            coin_id: self.coin.coin_id(),
+            other_asset_amount,
+            offered_amount: create_coin.amount,
+            delegated_puzzle_hash: ctx.tree_hash(delegated_puzzle).into(),
+        }))
+    }
+
+    // First of `takers` that can fill this offer (None for public offers)
+    pub fn allowed_taker(&self, takers: &[PartialTaker]) -> Option<PartialTaker> {
+        let taker_allowlist = self.info.taker_allowlist.as_ref()?;
+
+        takers
+            .iter()
+            .find(|taker| taker_allowlist.allows(taker))
+            .copied()
+    }
+
+    // 1-of-n spend through the given leaf (no asset layers)
+    pub fn leaf_spend(
+        &self,
+        ctx: &mut SpendContext,
+        leaf: Bytes32,
+        inner_spend: Spend,
+    ) -> Result<Spend, DriverError> {
+        let merkle_tree = self.info.merkle_tree()?;
         let inner_puzzle = ctx.curry(P2OneOfManyArgs::new(merkle_tree.root()))?;
-        let inner_solution = P2OneOfManySolution {
+        let inner_solution = ctx.alloc(&P2OneOfManySolution {
             merkle_proof: merkle_tree
-                .proof(self.info.maker_puzzle_hash)
+                .proof(leaf)
                 .ok_or(DriverError::InvalidMerkleProof)?,
             puzzle: inner_spend.puzzle,
             solution: inner_spend.solution,
+        })?;
+
+        Ok(Spend::new(inner_puzzle, inner_solution))
+    }
+
+    // The leaf is picked based on the inner puzzle, which has to be either the
+    //  clawback puzzle or one of the extra leaves
+    // Locked offers get the timelock wrapper added here, so the spend will only
+    //  be valid once the lock expires
+    pub fn clawback_leaf_spend(
+        &self,
+        ctx: &mut SpendContext,
+        inner_spend: Spend,
+    ) -> Result<Spend, DriverError> {
+        let puzzle_hash: Bytes32 = ctx.tree_hash(inner_spend.puzzle).into();
+        if puzzle_hash != self.info.clawback_puzzle_hash
+            && !self.info.extra_leaves.contains(&puzzle_hash)
+        {
+            return Err(DriverError::Custom(
+                "Inner spend does not match any clawback leaf".to_string(),
+            ));
+        }
+
+        let inner_spend = if let Some(clawback_lock) = self.info.clawback_lock {
+            let puzzle = ctx.curry(TimelockArgs {
+                condition_opcode: ASSERT_SECONDS_ABSOLUTE_OPCODE,
+                seconds: clawback_lock,
+                inner_puzzle: inner_spend.puzzle,
+            })?;
+            let solution = ctx.alloc(&TimelockSolution {
+                inner_solution: inner_spend.solution,
+            })?;
+            Spend::new(puzzle, solution)
+        } else {
+            inner_spend
         };
-        let inner_solution = ctx.alloc(&inner_solution)?;
+
+        self.leaf_spend(ctx, self.info.clawback_leaf(puzzle_hash), inner_spend)
+    }
+
+    pub fn claw_back(&self, ctx: &mut SpendContext, inner_spend: Spend) -> Result<(), DriverError> {
+        let p2_spend = self.clawback_leaf_spend(ctx, inner_spend)?;
+        self.spend_p2(ctx, p2_spend)
+    }
+
+    // Wraps a 1-of-n spend in the revocation & CAT layers (if any) and spends
+    //  the partial coin
+    pub fn spend_p2(&self, ctx: &mut SpendContext, p2_spend: Spend) -> Result<(), DriverError> {
+        let Spend {
+            puzzle: inner_puzzle,
+            solution: inner_solution,
+        } = p2_spend;
 
         let inner_solution = if self.info.offered_asset_info.hidden_puzzle_hash.is_some() {
             ctx.alloc(&RevocationSolution::new(
@@ -231,12 +461,12 @@ impl PartialOffer {
                     {
                         RevocationArgs::new(
                             hidden_puzzle_hash,
-                            self.info.inner_puzzle_hash().into(),
+                            self.info.inner_puzzle_hash()?.into(),
                         )
                         .curry_tree_hash()
                         .into()
                     } else {
-                        self.info.inner_puzzle_hash().into()
+                        self.info.inner_puzzle_hash()?.into()
                     },
                     amount: self.coin.amount,
                 },
@@ -251,207 +481,466 @@ impl PartialOffer {
         Ok(())
     }
 
+    // Payment to the receiver for a fill of `amount` (minus fees, if any)
     pub fn notatized_payment(
         &self,
         ctx: &mut SpendContext,
         amount: u64,
     ) -> Result<NotarizedPayment, DriverError> {
+        let Some(receiver_amount) = amount.checked_sub(self.info.total_fees(amount)) else {
+            return Err(DriverError::Custom(
+                "Partial offer fees exceed the payment".to_string(),
+            ));
+        };
+
         Ok(NotarizedPayment {
             nonce: self.coin.parent_coin_info,
             payments: vec![Payment::new(
-                self.info.maker_puzzle_hash,
-                amount,
-                ctx.hint(self.info.maker_puzzle_hash)?,
+                self.info.receiver_puzzle_hash,
+                receiver_amount,
+                ctx.hint(self.info.receiver_puzzle_hash)?,
             )],
         })
     }
 
-    pub fn quote(asked_asset_amount: u64, price_data: PartialPriceData) -> u64 {
-        asked_asset_amount * price_data.price_precision / price_data.precision
+    // Fee split offers assert one notarized payment per (non-zero) fee
+    pub fn fee_payments(
+        &self,
+        ctx: &mut SpendContext,
+        amount: u64,
+    ) -> Result<Vec<NotarizedPayment>, DriverError> {
+        let mut payments = Vec::with_capacity(self.info.fee_recipients.len());
+        for recipient in &self.info.fee_recipients {
+            let fee = recipient.fee(amount);
+            if fee == 0 {
+                continue;
+            }
+
+            payments.push(NotarizedPayment {
+                nonce: self.coin.parent_coin_info,
+                payments: vec![Payment::new(
+                    recipient.puzzle_hash,
+                    fee,
+                    ctx.hint(recipient.puzzle_hash)?,
+                )],
+            });
+        }
+
+        Ok(payments)
+    }
+
+    // All payments the taker has to make for a fill of `amount`
+    pub fn notarized_payments(
+        &self,
+        ctx: &mut SpendContext,
+        amount: u64,
+    ) -> Result<Vec<NotarizedPayment>, DriverError> {
+        let mut payments = vec![self.notatized_payment(ctx, amount)?];
+        payments.extend(self.fee_payments(ctx, amount)?);
+
+        Ok(payments)
+    }
+
+    // Price used for fills at `now` - bonding curves use the marginal price
+    //  at the coin's current amount
+    pub fn price_data_at(&self, now: u64) -> PartialPriceData {
+        if let Some(curve) = self.info.curve {
+            return curve.spot_price(self.coin.amount);
+        }
+
+        self.info.price_data_at(now)
+    }
+
+    // Largest amount of the offered asset a single fill can release
+    pub fn max_output(&self) -> u64 {
+        self.info
+            .curve
+            .map_or(self.coin.amount, |curve| curve.max_output(self.coin.amount))
+    }
+
+    // Quote for giving `input` of the requested asset at `now`; capped at the coin's amount
+    pub fn quote(&self, input: u64, now: u64) -> Result<Quote, QuoteError> {
+        let quote = if let Some(curve) = self.info.curve {
+            curve.quote(self.coin.amount, input)?
+        } else {
+            self.info.price_data_at(now).quote(input)?
+        };
+        let quote = if quote.output > self.coin.amount {
+            let full_fill = self.reverse_quote(self.coin.amount, now)?;
+            Quote {
+                unused: input - full_fill.input,
+                ..full_fill
+            }
+        } else {
+            quote
+        };
+
+        if quote.input <= self.info.min_other_asset_amount_minus_one {
+            return Err(QuoteError::BelowMinimum {
+                minimum: self.info.min_other_asset_amount_minus_one + 1,
+            });
+        }
+
+        Ok(quote)
     }
 
-    pub fn reverse_quote(offered_asset_amount: u64, price_data: PartialPriceData) -> u64 {
-        offered_asset_amount * price_data.precision / price_data.price_precision
+    // Quote for receiving `output` of the offered asset
+    pub fn reverse_quote(&self, output: u64, now: u64) -> Result<Quote, QuoteError> {
+        if let Some(curve) = self.info.curve {
+            return curve.reverse_quote(self.coin.amount, output);
+        }
+
+        self.info.price_data_at(now).reverse_quote(output)
     }
 
+    // With a referral, the taker's offer also covers the referral fee
     pub fn accept_offer(
         self,
         ctx: &mut SpendContext,
         offer: Offer,
+        now: u64,
+        referral: Option<PartialReferral>,
+        takers: &[PartialTaker],
+        authorizer: Option<&dyn FillAuthorizer>,
     ) -> Result<SpendBundle, DriverError> {
         // assumes ask/give amounts were calculated correctly
-        let offer_puzzle = ctx.alloc_mod::<SettlementPayment>()?;
-        if let Some(requested_asset_id) = self.info.requested_asset_info.asset_id {
+        let given_amount = if let Some(requested_asset_id) = self.info.requested_asset_info.asset_id
+        {
             // we're requesting a CAT
-            let Some(cats) = offer.offered_coins().cats.get(&requested_asset_id) else {
+            let Some(cat) = offer
+                .offered_coins()
+                .cats
+                .get(&requested_asset_id)
+                .and_then(|cats| cats.first())
+            else {
                 return Err(DriverError::IncompatibleAssetInfo);
             };
 
-            let cat = cats[0];
-            let (my_spend, notarized_payment) = self.partial_coin_spend(
-                ctx,
-                cat.coin.amount,
-                Some(CreateCoin::<Memos> {
-                    puzzle_hash: SETTLEMENT_PAYMENT_HASH.into(),
-                    amount: Self::quote(cat.coin.amount, self.info.price_data),
-                    memos: Memos::None,
-                }),
-            )?;
-            ctx.spend(self.coin, my_spend)?;
+            cat.coin.amount
+        } else {
+            // we're requesting XCH
+            let Some(given_coin) = offer.offered_coins().xch.first() else {
+                return Err(DriverError::IncompatibleAssetInfo);
+            };
 
-            let inner_spend = Spend::new(
-                offer_puzzle,
-                ctx.alloc(&SettlementPaymentsSolution {
-                    notarized_payments: vec![notarized_payment],
-                })?,
-            );
-            let _ = Cat::spend_all(ctx, &[CatSpend::new(cat, inner_spend)])?;
+            given_coin
+                .amount
+                .checked_sub(self.info.required_fee.unwrap_or(0))
+                .ok_or(DriverError::IncompatibleAssetInfo)?
+        };
 
-            if self.info.required_fee.unwrap_or(0) > 0 {
-                // offer also gives XCH to pay the required fee
-                let Some(given_xch_coin) = offer.offered_coins().xch.first() else {
-                    return Err(DriverError::IncompatibleAssetInfo);
+        let other_asset_amount = if let Some(referral) = referral {
+            referral.max_input(given_amount)
+        } else {
+            given_amount
+        };
+
+        Self::accept_offers(
+            ctx,
+            vec![(self, other_asset_amount)],
+            offer,
+            now,
+            referral,
+            takers,
+            authorizer,
+        )
+    }
+
+    // Fills several partial offers for the same pair with a single taker offer
+    // Each entry is (partial offer, other asset amount paid to its maker)
+    // Amounts the partial offer can't fully use (e.g., over-fills) are rejected,
+    //  as the rest would be left in the taker's offer
+    // The taker offer has to give the sum of all amounts (plus required fees)
+    //  and request the sum of all quoted outputs
+    // Offers are priced (and spent) at `now`, which should be close to the
+    //  timestamp of the block that will include the spend bundle
+    // If there's a referral, the taker also pays its fee for each fill (so the
+    //  taker offer needs to give that much more)
+    // Private offers are filled as the first of `takers` on their allowlist -
+    //  the returned spend bundle still needs that taker's signature
+    // Co-signed offers get their fills signed by `authorizer`
+    pub fn accept_offers(
+        ctx: &mut SpendContext,
+        fills: Vec<(Self, u64)>,
+        offer: Offer,
+        now: u64,
+        referral: Option<PartialReferral>,
+        takers: &[PartialTaker],
+        authorizer: Option<&dyn FillAuthorizer>,
+    ) -> Result<SpendBundle, DriverError> {
+        let Some((first_offer, _)) = fills.first() else {
+            return Err(DriverError::Custom("No partial offers to fill".to_string()));
+        };
+        let offered_asset_info = first_offer.info.offered_asset_info;
+        let requested_asset_info = first_offer.info.requested_asset_info;
+        if fills.iter().any(|(partial_offer, _)| {
+            partial_offer.info.offered_asset_info != offered_asset_info
+                || partial_offer.info.requested_asset_info != requested_asset_info
+        }) {
+            return Err(DriverError::IncompatibleAssetInfo);
+        }
+
+        let offer_puzzle = ctx.alloc_mod::<SettlementPayment>()?;
+        let mut notarized_payments = Vec::with_capacity(fills.len());
+        let mut settlement_coins = Vec::with_capacity(fills.len());
+        let mut authorizer_signature = Signature::default();
+        for (partial_offer, other_asset_amount) in &fills {
+            let quote = partial_offer.quote(*other_asset_amount, now)?;
+            if quote.unused > 0 {
+                return Err(DriverError::Custom(format!(
+                    "Partial offer only accepts {} of the {} mojos given",
+                    quote.input, other_asset_amount
+                )));
+            }
+
+            let create_coin = CreateCoin::<Memos> {
+                puzzle_hash: SETTLEMENT_PAYMENT_HASH.into(),
+                amount: quote.output,
+                memos: Memos::None,
+            };
+            if let Some(authorization) =
+                partial_offer.fill_authorization(ctx, quote.input, &create_coin)?
+            {
+                let Some(authorizer) = authorizer else {
+                    return Err(DriverError::Custom(
+                        "Partial offer fills have to be co-signed by a fill authorizer".to_string(),
+                    ));
                 };
 
-                let spend = Spend::new(
+                authorizer_signature += &authorizer.authorize(&authorization)?;
+            }
+
+            let (my_spend, offer_notarized_payments) = partial_offer.partial_coin_spend(
+                ctx,
+                quote.input,
+                Some(create_coin),
+                now,
+                partial_offer.allowed_taker(takers),
+            )?;
+            ctx.spend(partial_offer.coin, my_spend)?;
+
+            notarized_payments.extend(offer_notarized_payments);
+            if let Some(referral) = referral
+                && let Some(referral_payment) =
+                    referral.notarized_payment(ctx, partial_offer.coin.coin_id(), quote.input)?
+            {
+                notarized_payments.push(referral_payment);
+            }
+            settlement_coins.push((partial_offer, quote.output));
+        }
+
+        // taker pays makers - first coin holds all notarized payments
+        if let Some(requested_asset_id) = requested_asset_info.asset_id {
+            // we're requesting a CAT
+            let Some(cats) = offer.offered_coins().cats.get(&requested_asset_id) else {
+                return Err(DriverError::IncompatibleAssetInfo);
+            };
+
+            let mut cat_spends = Vec::with_capacity(cats.len());
+            for (i, cat) in cats.iter().enumerate() {
+                let inner_spend = Spend::new(
                     offer_puzzle,
-                    ctx.alloc(&SettlementPaymentsSolution::<NodePtr> {
-                        notarized_payments: vec![],
+                    ctx.alloc(&SettlementPaymentsSolution {
+                        notarized_payments: if i == 0 {
+                            notarized_payments.clone()
+                        } else {
+                            vec![]
+                        },
                     })?,
                 );
-                ctx.spend(*given_xch_coin, spend)?;
+                cat_spends.push(CatSpend::new(*cat, inner_spend));
+            }
+            let _ = Cat::spend_all(ctx, &cat_spends)?;
+
+            if fills
+                .iter()
+                .any(|(partial_offer, _)| partial_offer.info.required_fee.unwrap_or(0) > 0)
+            {
+                // offer also gives XCH to pay the required fees
+                if offer.offered_coins().xch.is_empty() {
+                    return Err(DriverError::IncompatibleAssetInfo);
+                }
+
+                for given_xch_coin in &offer.offered_coins().xch {
+                    let spend = Spend::new(
+                        offer_puzzle,
+                        ctx.alloc(&SettlementPaymentsSolution::<NodePtr> {
+                            notarized_payments: vec![],
+                        })?,
+                    );
+                    ctx.spend(*given_xch_coin, spend)?;
+                }
             }
         } else {
             // we're requesting XCH
-            let Some(given_coin) = offer.offered_coins().xch.first() else {
+            if offer.offered_coins().xch.is_empty() {
                 return Err(DriverError::IncompatibleAssetInfo);
-            };
-
-            let other_asset_amount = given_coin.amount - self.info.required_fee.unwrap_or(0);
-
-            let (my_spend, notarized_payment) = self.partial_coin_spend(
-                ctx,
-                other_asset_amount,
-                Some(CreateCoin::<Memos> {
-                    puzzle_hash: SETTLEMENT_PAYMENT_HASH.into(),
-                    amount: Self::quote(other_asset_amount, self.info.price_data),
-                    memos: Memos::None,
-                }),
-            )?;
-            ctx.spend(self.coin, my_spend)?;
+            }
 
-            let spend = Spend::new(
-                offer_puzzle,
-                ctx.alloc(&SettlementPaymentsSolution {
-                    notarized_payments: vec![notarized_payment],
-                })?,
-            );
-            ctx.spend(*given_coin, spend)?;
+            for (i, given_coin) in offer.offered_coins().xch.iter().enumerate() {
+                let spend = Spend::new(
+                    offer_puzzle,
+                    ctx.alloc(&SettlementPaymentsSolution {
+                        notarized_payments: if i == 0 {
+                            notarized_payments.clone()
+                        } else {
+                            vec![]
+                        },
+                    })?,
+                );
+                ctx.spend(*given_coin, spend)?;
+            }
         };
 
-        if let Some(offered_asset_id) = self.info.offered_asset_info.asset_id {
+        // partial coins pay the taker - first settlement coin holds all requested payments
+        if let Some(offered_asset_id) = offered_asset_info.asset_id {
             // we're offering a CAT
-            let Some(notarized_payments) = offer.requested_payments().cats.get(&offered_asset_id)
+            let Some(requested_payments) = offer.requested_payments().cats.get(&offered_asset_id)
             else {
                 return Err(DriverError::IncompatibleAssetInfo);
             };
-            let inner_spend = Spend::new(
-                offer_puzzle,
-                ctx.alloc(&SettlementPaymentsSolution {
-                    notarized_payments: notarized_payments.clone(),
-                })?,
-            );
 
-            let cat = Cat::new(
-                Coin::new(
-                    self.coin.coin_id(),
-                    PartialOfferInfo::full_asset_puzzle_hash(
-                        self.info.offered_asset_info,
+            let mut cat_spends = Vec::with_capacity(settlement_coins.len());
+            for (i, (partial_offer, amount)) in settlement_coins.into_iter().enumerate() {
+                let inner_spend = Spend::new(
+                    offer_puzzle,
+                    ctx.alloc(&SettlementPaymentsSolution {
+                        notarized_payments: if i == 0 {
+                            requested_payments.clone()
+                        } else {
+                            vec![]
+                        },
+                    })?,
+                );
+
+                let cat = Cat::new(
+                    Coin::new(
+                        partial_offer.coin.coin_id(),
+                        PartialOfferInfo::full_asset_puzzle_hash(
+                            offered_asset_info,
+                            SETTLEMENT_PAYMENT_HASH.into(),
+                        ),
+                        amount,
+                    ),
+                    Some(partial_offer.child_lineage_proof()?),
+                    CatInfo::new(
+                        offered_asset_id,
+                        offered_asset_info.hidden_puzzle_hash,
                         SETTLEMENT_PAYMENT_HASH.into(),
                     ),
-                    notarized_payments[0].payments[0].amount,
-                ),
-                Some(LineageProof {
-                    parent_parent_coin_info: self.coin.parent_coin_info,
-                    parent_inner_puzzle_hash: if let Some(hidden_puzzle_hash) =
-                        self.info.offered_asset_info.hidden_puzzle_hash
-                    {
-                        RevocationArgs::new(
-                            hidden_puzzle_hash,
-                            self.info.inner_puzzle_hash().into(),
-                        )
-                        .curry_tree_hash()
-                        .into()
-                    } else {
-                        self.info.inner_puzzle_hash().into()
-                    },
-                    parent_amount: self.coin.amount,
-                }),
-                CatInfo::new(
-                    offered_asset_id,
-                    self.info.offered_asset_info.hidden_puzzle_hash,
-                    SETTLEMENT_PAYMENT_HASH.into(),
-                ),
-            );
+                );
+                cat_spends.push(CatSpend::new(cat, inner_spend));
+            }
 
-            let _ = Cat::spend_all(ctx, &[CatSpend::new(cat, inner_spend)])?;
+            let _ = Cat::spend_all(ctx, &cat_spends)?;
         } else {
             // we're offering XCH
-            let coin_amount: u64 = offer
-                .requested_payments()
-                .xch
-                .iter()
-                .map(|p| p.payments.iter().map(|p| p.amount).sum::<u64>())
-                .sum();
-            let spend = Spend::new(
-                offer_puzzle,
-                ctx.alloc(&SettlementPaymentsSolution {
-                    notarized_payments: offer.requested_payments().xch.clone(),
-                })?,
-            );
-            ctx.spend(
-                Coin::new(
-                    self.coin.coin_id(),
-                    SETTLEMENT_PAYMENT_HASH.into(),
-                    coin_amount,
-                ),
-                spend,
-            )?;
+            for (i, (partial_offer, amount)) in settlement_coins.into_iter().enumerate() {
+                let spend = Spend::new(
+                    offer_puzzle,
+                    ctx.alloc(&SettlementPaymentsSolution {
+                        notarized_payments: if i == 0 {
+                            offer.requested_payments().xch.clone()
+                        } else {
+                            vec![]
+                        },
+                    })?,
+                );
+                ctx.spend(
+                    Coin::new(
+                        partial_offer.coin.coin_id(),
+                        SETTLEMENT_PAYMENT_HASH.into(),
+                        amount,
+                    ),
+                    spend,
+                )?;
+            }
         }
 
-        let spend_bundle = SpendBundle::new(ctx.take(), Signature::default());
-        Ok(self.take(offer.take(spend_bundle)))
+        let spend_bundle = offer.take(SpendBundle::new(ctx.take(), authorizer_signature));
+        Ok(fills
+            .into_iter()
+            .fold(spend_bundle, |spend_bundle, (partial_offer, _)| {
+                partial_offer.take_shared(spend_bundle)
+            }))
     }
 
-    pub fn child(&self, child_amount: u64) -> Self {
-        Self {
+    // Picks partial offers best-price-first until `input` of the requested asset is used up
+    // Offers that can't be filled with what's left (e.g., minimum amount) are skipped
+    // Referral fees are paid out of `input` as well
+    pub fn sweep(
+        mut offers: Vec<Self>,
+        input: u64,
+        now: u64,
+        referral: Option<&PartialReferral>,
+    ) -> Result<Vec<(Self, Quote)>, QuoteError> {
+        offers.sort_by(|a, b| b.price_data_at(now).cmp_rate(&a.price_data_at(now)));
+
+        let mut remaining = input;
+        let mut fills = Vec::new();
+        for offer in offers {
+            if remaining == 0 {
+                break;
+            }
+
+            let available = if let Some(referral) = referral {
+                referral.max_input(remaining)
+            } else {
+                remaining
+            };
+            let quote = match offer.quote(available, now) {
+                Ok(quote) => quote.with_referral(referral),
+                Err(QuoteError::BelowMinimum { .. }) => continue,
+                Err(err) => return Err(err),
+            };
+            if quote.output == 0 {
+                continue;
+            }
+
+            remaining -= quote.total_input();
+            fills.push((offer, Quote { unused: 0, ..quote }));
+        }
+
+        Ok(fills)
+    }
+
+    // Lineage proof of any CAT created by the partial coin
+    pub fn child_lineage_proof(&self) -> Result<LineageProof, DriverError> {
+        let inner_puzzle_hash = self.info.inner_puzzle_hash()?;
+
+        Ok(LineageProof {
+            parent_parent_coin_info: self.coin.parent_coin_info,
+            parent_inner_puzzle_hash: if let Some(hidden_puzzle_hash) =
+                self.info.offered_asset_info.hidden_puzzle_hash
+            {
+                RevocationArgs::new(hidden_puzzle_hash, inner_puzzle_hash.into())
+                    .curry_tree_hash()
+                    .into()
+            } else {
+                inner_puzzle_hash.into()
+            },
+            parent_amount: self.coin.amount,
+        })
+    }
+
+    pub fn child(&self, child_amount: u64) -> Result<Self, DriverError> {
+        let lineage_proof = if self.info.lineage_proof.is_some() {
+            Some(self.child_lineage_proof()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
             coin: Coin::new(self.coin.coin_id(), self.coin.puzzle_hash, child_amount),
-            info: self
-                .info
-                .clone()
-                .with_lineage_proof(self.info.lineage_proof.map(|_| LineageProof {
-                    parent_parent_coin_info: self.coin.parent_coin_info,
-                    parent_inner_puzzle_hash: if let Some(hidden_puzzle_hash) =
-                        self.info.offered_asset_info.hidden_puzzle_hash
-                    {
-                        RevocationArgs::new(
-                            hidden_puzzle_hash,
-                            self.info.inner_puzzle_hash().into(),
-                        )
-                        .curry_tree_hash()
-                        .into()
-                    } else {
-                        self.info.inner_puzzle_hash().into()
-                    },
-                    parent_amount: self.coin.amount,
-                })),
+            info: self.info.clone().with_lineage_proof(lineage_proof),
             spend_bundle: SpendBundle::new(Vec::new(), Signature::default()),
-        }
+        })
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use chia::protocol::{Bytes, Program};
     use chia_wallet_sdk::{
         driver::{
             AssetInfo, CatAssetInfo, OfferCoins, RequestedPayments, SpendWithConditions,
@@ -460,10 +949,16 @@ mod tests {
         test::{Benchmark, Simulator},
         types::{Conditions, announcement_id},
     };
-    use clvm_traits::clvm_quote;
+    use clvm_traits::{clvm_list, clvm_quote};
     use rstest::*;
 
-    use crate::PartialOfferAssetInfo;
+    use slot_machine::get_constants;
+
+    use crate::{
+        FEE_SPLIT_BPS_DENOMINATOR, LadderFunding, LocalFillAuthorizer, MAX_DUTCH_STEPS,
+        PartialCurve, PartialDutchAuction, PartialFeeRecipient, PartialOfferAssetInfo,
+        PartialOfferSpend, PartialPriceData, PartialTakerAllowlist,
+    };
 
     use super::*;
 
@@ -480,6 +975,892 @@ mod tests {
         Ok(())
     }
 
+    pub const TAKER_PUZZLE_HASH: Bytes32 = Bytes32::new([3; 32]);
+
+    pub fn sim_partial_offer(
+        sim: &mut Simulator,
+        info: PartialOfferInfo,
+        amount: u64,
+    ) -> Result<PartialOffer, DriverError> {
+        let coin = sim.new_coin(info.puzzle_hash()?, amount);
+        PartialOffer::new(coin.parent_coin_info, amount, info)
+    }
+
+    // Takes `output` from the partial offer for `input` paid by a settlement coin
+    // Returns the coin spends (partial coin first, payment second) and the fill
+    //  parsed back from the partial coin spend
+    pub fn fill_partial_offer(
+        ctx: &mut SpendContext,
+        sim: &mut Simulator,
+        partial_offer: &PartialOffer,
+        input: u64,
+        output: u64,
+        now: u64,
+        taker: Option<PartialTaker>,
+    ) -> Result<(Vec<CoinSpend>, PartialOfferSpend), DriverError> {
+        let (spend, notarized_payments) = partial_offer.partial_coin_spend(
+            ctx,
+            input,
+            Some(CreateCoin::<Memos> {
+                puzzle_hash: TAKER_PUZZLE_HASH,
+                amount: output,
+                memos: Memos::None,
+            }),
+            now,
+            taker,
+        )?;
+        ctx.spend(partial_offer.coin, spend)?;
+
+        let payment_coin = sim.new_coin(SETTLEMENT_PAYMENT_HASH.into(), input);
+        let offer_puzzle = ctx.alloc_mod::<SettlementPayment>()?;
+        let offer_solution = ctx.alloc(&SettlementPaymentsSolution { notarized_payments })?;
+        ctx.spend(payment_coin, Spend::new(offer_puzzle, offer_solution))?;
+
+        let coin_spends = ctx.take();
+        let reconstructed = PartialOffer::from_parent_spend(ctx, &coin_spends[0], &[])?
+            .ok_or_else(|| DriverError::Custom("Fill was not parsed".to_string()))?;
+
+        Ok((coin_spends, reconstructed))
+    }
+
+    // Round-trips `info` through its hint, which has to give the same puzzle hash
+    pub fn parse_hint(
+        ctx: &mut SpendContext,
+        info: &PartialOfferInfo,
+    ) -> Result<PartialOfferInfo, DriverError> {
+        let hint = ctx.alloc(&info.to_hint())?;
+        let parsed = PartialOfferInfo::from_hint(&ctx.extract(hint)?)
+            .ok_or_else(|| DriverError::Custom("Hint was not parsed".to_string()))?;
+        assert_eq!(parsed.puzzle_hash()?, info.puzzle_hash()?);
+
+        Ok(parsed)
+    }
+
+    // XCH for CAT offer that asks `asked` for all `offered` mojos
+    pub fn offer_with_price(
+        parent_coin_id: Bytes32,
+        offered: u64,
+        asked: u64,
+        expiration: Option<u64>,
+    ) -> Result<PartialOffer, DriverError> {
+        PartialOffer::new(
+            parent_coin_id,
+            offered,
+            PartialOfferInfo::new(
+                None,
+                PartialOfferAssetInfo::xch(),
+                PartialOfferAssetInfo::cat(Bytes32::default(), None),
+                Bytes32::default(),
+                expiration,
+                None,
+                PartialPriceData {
+                    price_precision: offered,
+                    precision: asked,
+                },
+                9,
+            ),
+        )
+    }
+
+    #[test]
+    fn test_sweep_best_price_first() -> anyhow::Result<()> {
+        let cheap = offer_with_price(Bytes32::new([1; 32]), 1000, 100, None)?;
+        let expensive = offer_with_price(Bytes32::new([2; 32]), 1000, 200, None)?;
+
+        let fills = PartialOffer::sweep(vec![expensive.clone(), cheap.clone()], 150, 0, None)?;
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[0].0.coin, cheap.coin);
+        assert_eq!((fills[0].1.input, fills[0].1.output), (100, 1000));
+        assert_eq!(fills[1].0.coin, expensive.coin);
+        assert_eq!((fills[1].1.input, fills[1].1.output), (50, 250));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accept_offer_rejects_over_fill() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        // 100 CAT mojos already buy the whole offer
+        let partial_offer = offer_with_price(Bytes32::new([1; 32]), 1000, 100, None)?;
+        let mut offered_coins = OfferCoins::new();
+        offered_coins.cats.insert(
+            Bytes32::default(),
+            vec![Cat::new(
+                Coin::new(Bytes32::new([4; 32]), SETTLEMENT_PAYMENT_HASH.into(), 150),
+                None,
+                CatInfo::new(Bytes32::default(), None, SETTLEMENT_PAYMENT_HASH.into()),
+            )],
+        );
+        let offer = Offer::new(
+            SpendBundle::new(vec![], Signature::default()),
+            offered_coins,
+            RequestedPayments::new(),
+            AssetInfo::new(),
+        );
+
+        let Err(DriverError::Custom(message)) =
+            partial_offer.accept_offer(ctx, offer, 0, None, &[], None)
+        else {
+            panic!("over-filling a partial offer should fail");
+        };
+        assert_eq!(
+            message,
+            "Partial offer only accepts 100 of the 150 mojos given"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accept_ladder_rungs() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let rungs = [1, 2]
+            .into_iter()
+            .map(|precision| {
+                (
+                    PartialOfferInfo::new(
+                        None,
+                        PartialOfferAssetInfo::xch(),
+                        PartialOfferAssetInfo::xch(),
+                        Bytes32::new([2; 32]),
+                        None,
+                        None,
+                        PartialPriceData {
+                            price_precision: 1,
+                            precision,
+                        },
+                        0,
+                    ),
+                    1_000,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut funding_conditions = Conditions::new();
+        for payment in PartialOffer::ladder_funding_payments(ctx, &rungs)? {
+            funding_conditions =
+                funding_conditions.create_coin(payment.puzzle_hash, payment.amount, Memos::None);
+        }
+        let funding_puzzle = ctx.alloc(&clvm_quote!(funding_conditions))?;
+        let funding_coin = sim.new_coin(ctx.tree_hash(funding_puzzle).into(), 2_000);
+        ctx.spend(funding_coin, Spend::new(funding_puzzle, NodePtr::NIL))?;
+
+        // both rungs carry the same creation spends, in any order
+        let offers =
+            PartialOffer::fund_ladder(ctx, LadderFunding::Xch(funding_coin.coin_id()), rungs)?;
+        let coin_spends = ctx.take();
+        let mut reversed_coin_spends = coin_spends.clone();
+        reversed_coin_spends.reverse();
+        let offers = offers
+            .into_iter()
+            .zip([coin_spends.clone(), reversed_coin_spends])
+            .map(|(partial_offer, coin_spends)| PartialOffer {
+                spend_bundle: SpendBundle::new(coin_spends, Signature::default()),
+                ..partial_offer
+            })
+            .collect::<Vec<_>>();
+
+        let taker_coin = sim.new_coin(SETTLEMENT_PAYMENT_HASH.into(), 600);
+        let mut offered_coins = OfferCoins::new();
+        offered_coins.xch.push(taker_coin);
+        let mut requested_payments = RequestedPayments::new();
+        requested_payments.xch.push(NotarizedPayment {
+            nonce: Bytes32::default(),
+            payments: vec![Payment::new(TAKER_PUZZLE_HASH, 450, Memos::None)],
+        });
+        let offer = Offer::new(
+            SpendBundle::new(vec![], Signature::default()),
+            offered_coins,
+            requested_payments,
+            AssetInfo::new(),
+        );
+
+        let spend_bundle = PartialOffer::accept_offers(
+            ctx,
+            vec![(offers[0].clone(), 300), (offers[1].clone(), 300)],
+            offer,
+            0,
+            None,
+            &[],
+            None,
+        )?;
+        for coin_spend in &coin_spends {
+            assert_eq!(
+                spend_bundle
+                    .coin_spends
+                    .iter()
+                    .filter(|other| *other == coin_spend)
+                    .count(),
+                1
+            );
+        }
+
+        sim.new_transaction(spend_bundle)?;
+        assert!(
+            sim.coin_state(offers[0].child(700)?.coin.coin_id())
+                .is_some()
+        );
+        assert!(
+            sim.coin_state(offers[1].child(850)?.coin.coin_id())
+                .is_some()
+        );
+        let first_settlement_coin = Coin::new(
+            offers[0].coin.coin_id(),
+            SETTLEMENT_PAYMENT_HASH.into(),
+            300,
+        );
+        assert!(
+            sim.coin_state(
+                Coin::new(first_settlement_coin.coin_id(), TAKER_PUZZLE_HASH, 450).coin_id()
+            )
+            .is_some()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dutch_auction_steps() -> anyhow::Result<()> {
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::cat(Bytes32::default(), None),
+            Bytes32::default(),
+            Some(500),
+            None,
+            PartialPriceData {
+                price_precision: 1000,
+                precision: 300,
+            },
+            0,
+        );
+        let auction = PartialDutchAuction {
+            start_time: 100,
+            end_time: 200,
+            end_precision: 100,
+            steps: 3,
+        };
+        let dutch_info = info.clone().with_dutch_auction(Some(auction));
+
+        let steps = dutch_info.steps();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(
+            steps
+                .iter()
+                .map(|step| (step.price_data.precision, step.start_time, step.end_time))
+                .collect::<Vec<_>>(),
+            vec![
+                (300, None, Some(150)),
+                (200, Some(150), Some(200)),
+                (100, Some(200), Some(500))
+            ]
+        );
+        assert_eq!(dutch_info.price_data_at(0).precision, 300);
+        assert_eq!(dutch_info.price_data_at(199).precision, 200);
+        assert_eq!(dutch_info.price_data_at(1000).precision, 100);
+        assert!(dutch_info.active_step(500).is_none());
+        assert_eq!(dutch_info.leaves()?.len(), 4);
+        assert_ne!(dutch_info.inner_puzzle_hash()?, info.inner_puzzle_hash()?);
+
+        // hints without an auction keep their original encoding
+        let mut ctx = SpendContext::new();
+        let hint = ctx.alloc(&info.to_hint())?;
+        let old_hint = ctx.alloc(&clvm_list!(
+            info.lineage_proof,
+            info.offered_asset_info,
+            info.requested_asset_info,
+            info.price_data,
+            info.clawback_puzzle_hash,
+            info.quoted_inner_conditions(),
+            info.min_other_asset_amount_minus_one
+        ))?;
+        assert_eq!(ctx.tree_hash(hint), ctx.tree_hash(old_hint));
+
+        assert_eq!(
+            parse_hint(&mut ctx, &dutch_info)?.dutch_auction,
+            Some(auction)
+        );
+
+        // step counts are capped
+        let mut hint = dutch_info.to_hint();
+        hint.dutch_auction = Some(PartialDutchAuction {
+            steps: MAX_DUTCH_STEPS + 1,
+            ..auction
+        });
+        assert!(PartialOfferInfo::from_hint(&hint).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dutch_auction_fills() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            Bytes32::new([2; 32]),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 3,
+            },
+            0,
+        )
+        .with_dutch_auction(Some(PartialDutchAuction {
+            start_time: 1_000,
+            end_time: 2_000,
+            end_precision: 1,
+            steps: 3,
+        }));
+        let partial_offer = sim_partial_offer(&mut sim, info, 1_000)?;
+
+        // first step, before the boundary at 1,500
+        let quote = partial_offer.quote(600, 0)?;
+        assert_eq!(quote.output, 200);
+        let (coin_spends, _) =
+            fill_partial_offer(ctx, &mut sim, &partial_offer, 600, quote.output, 0, None)?;
+        sim.spend_coins(coin_spends, &[])?;
+        let partial_offer = partial_offer.child(800)?;
+        assert!(sim.coin_state(partial_offer.coin.coin_id()).is_some());
+
+        // the first step's leaf expires at the boundary, so its price is stale
+        sim.pass_time(1_600);
+        let (coin_spends, _) =
+            fill_partial_offer(ctx, &mut sim, &partial_offer, 600, 200, 0, None)?;
+        assert!(sim.spend_coins(coin_spends, &[]).is_err());
+
+        // second step
+        let quote = partial_offer.quote(600, 1_600)?;
+        assert_eq!(quote.output, 300);
+        let (coin_spends, reconstructed) = fill_partial_offer(
+            ctx,
+            &mut sim,
+            &partial_offer,
+            600,
+            quote.output,
+            1_600,
+            None,
+        )?;
+        assert_eq!(reconstructed.fill.offered_amount, 300);
+        sim.spend_coins(coin_spends, &[])?;
+        assert!(
+            sim.coin_state(partial_offer.child(500)?.coin.coin_id())
+                .is_some()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_separate_receiver() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let clawback_puzzle_hash = Bytes32::new([2; 32]);
+        let receiver_puzzle_hash = Bytes32::new([3; 32]);
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            clawback_puzzle_hash,
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        );
+        let receiver_info = info.clone().with_receiver_puzzle_hash(receiver_puzzle_hash);
+        assert_ne!(
+            receiver_info.inner_puzzle_hash()?,
+            info.inner_puzzle_hash()?
+        );
+        assert_eq!(receiver_info.leaves()?.last(), Some(&clawback_puzzle_hash));
+
+        // the receiver is only part of the hint when it differs
+        assert_eq!(info.to_hint().receiver_puzzle_hash, None);
+        let parsed = parse_hint(ctx, &receiver_info)?;
+        assert_eq!(parsed.receiver_puzzle_hash, receiver_puzzle_hash);
+        assert_eq!(parsed.clawback_puzzle_hash, clawback_puzzle_hash);
+
+        let payment = PartialOffer::new(Bytes32::default(), 1_000, receiver_info)?
+            .notatized_payment(ctx, 500)?;
+        assert_eq!(payment.payments[0].puzzle_hash, receiver_puzzle_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fee_split_fills() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let maker_puzzle_hash = Bytes32::new([2; 32]);
+        let fee_recipients = vec![
+            PartialFeeRecipient {
+                puzzle_hash: Bytes32::new([4; 32]),
+                bps: 100,
+            },
+            PartialFeeRecipient {
+                puzzle_hash: Bytes32::new([5; 32]),
+                bps: 25,
+            },
+        ];
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            maker_puzzle_hash,
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 2,
+            },
+            0,
+        )
+        .with_fee_recipients(fee_recipients.clone());
+        let partial_offer = sim_partial_offer(&mut sim, info.clone(), 10_000)?;
+
+        // fees come out of the maker's proceeds, so the taker's quote is unchanged
+        let quote = partial_offer.quote(10_000, 0)?;
+        assert_eq!(quote.output, 5_000);
+
+        let (coin_spends, reconstructed) = fill_partial_offer(
+            ctx,
+            &mut sim,
+            &partial_offer,
+            quote.input,
+            quote.output,
+            0,
+            None,
+        )?;
+        assert_eq!(reconstructed.offer.info.fee_recipients, fee_recipients);
+        assert_eq!(reconstructed.fill.offered_amount, quote.output);
+
+        let payment_coin_id = coin_spends[1].coin.coin_id();
+        sim.spend_coins(coin_spends, &[])?;
+        for (puzzle_hash, amount) in [
+            (maker_puzzle_hash, 9_875),
+            (fee_recipients[0].puzzle_hash, 100),
+            (fee_recipients[1].puzzle_hash, 25),
+        ] {
+            let payment = Coin::new(payment_coin_id, puzzle_hash, amount);
+            assert!(sim.coin_state(payment.coin_id()).is_some());
+        }
+
+        // hints carry the fee recipients
+        assert_eq!(parse_hint(ctx, &info)?.fee_recipients, fee_recipients);
+
+        // ...as long as the receiver gets something and each recipient is paid once
+        for invalid_recipients in [
+            vec![fee_recipients[0], fee_recipients[0]],
+            vec![PartialFeeRecipient {
+                puzzle_hash: Bytes32::new([4; 32]),
+                bps: FEE_SPLIT_BPS_DENOMINATOR,
+            }],
+            vec![
+                PartialFeeRecipient {
+                    puzzle_hash: Bytes32::new([4; 32]),
+                    bps: u64::MAX,
+                },
+                fee_recipients[1],
+            ],
+        ] {
+            let mut hint = info.to_hint();
+            hint.fee_recipients = invalid_recipients;
+            assert!(PartialOfferInfo::from_hint(&hint).is_none());
+        }
+
+        // fees larger than the payment are an error instead of an underflow
+        let partial_offer = PartialOffer::new(
+            Bytes32::default(),
+            10_000,
+            info.with_fee_recipients(vec![PartialFeeRecipient {
+                puzzle_hash: Bytes32::new([4; 32]),
+                bps: u64::MAX,
+            }]),
+        )?;
+        assert!(partial_offer.notatized_payment(ctx, 10_000).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_taker_allowlist() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let allowed_bls = sim.bls(0);
+        let stranger_bls = sim.bls(0);
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            Bytes32::new([2; 32]),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        )
+        .with_taker_allowlist(Some(PartialTakerAllowlist {
+            keys: vec![allowed_bls.pk],
+            puzzle_hashes: vec![],
+        }));
+        let partial_offer = sim_partial_offer(&mut sim, info.clone(), 1_000)?;
+
+        for taker in [None, Some(PartialTaker::Key(stranger_bls.pk))] {
+            assert!(fill_partial_offer(ctx, &mut sim, &partial_offer, 400, 400, 0, taker).is_err());
+        }
+        let takers = [
+            PartialTaker::Key(stranger_bls.pk),
+            PartialTaker::Key(allowed_bls.pk),
+        ];
+        assert_eq!(
+            partial_offer.allowed_taker(&takers),
+            Some(PartialTaker::Key(allowed_bls.pk))
+        );
+
+        let (coin_spends, reconstructed) = fill_partial_offer(
+            ctx,
+            &mut sim,
+            &partial_offer,
+            400,
+            400,
+            0,
+            partial_offer.allowed_taker(&takers),
+        )?;
+        assert_eq!(
+            reconstructed.offer.info.taker_allowlist,
+            info.taker_allowlist
+        );
+
+        // the allowed taker has to sign the partial coin id
+        assert!(sim.spend_coins(coin_spends.clone(), &[]).is_err());
+        sim.spend_coins(coin_spends, std::slice::from_ref(&allowed_bls.sk))?;
+        assert!(
+            sim.coin_state(partial_offer.child(600)?.coin.coin_id())
+                .is_some()
+        );
+
+        // hints carry the allowlist
+        assert_eq!(
+            parse_hint(ctx, &info)?.taker_allowlist,
+            info.taker_allowlist
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_conditions() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            Bytes32::new([2; 32]),
+            Some(1_000_000),
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        )
+        .with_extra_conditions(
+            Conditions::new()
+                .create_puzzle_announcement(Bytes::new(b"launch".to_vec()))
+                .create_coin_announcement(Bytes::new(b"partial".to_vec())),
+        );
+        assert_ne!(
+            info.inner_puzzle_hash()?,
+            info.clone()
+                .with_extra_conditions(Conditions::new())
+                .inner_puzzle_hash()?
+        );
+        let partial_offer = sim_partial_offer(&mut sim, info.clone(), 1_000)?;
+
+        let (coin_spends, reconstructed) =
+            fill_partial_offer(ctx, &mut sim, &partial_offer, 400, 400, 0, None)?;
+        assert_eq!(reconstructed.offer.info.expiration, Some(1_000_000));
+        assert_eq!(
+            reconstructed.offer.info.extra_conditions,
+            info.extra_conditions
+        );
+
+        sim.spend_coins(coin_spends, &[])?;
+        assert!(
+            sim.coin_state(partial_offer.child(600)?.coin.coin_id())
+                .is_some()
+        );
+
+        // hints keep conditions they don't know about
+        let info = PartialOfferInfo {
+            required_fee: Some(100),
+            ..info
+        };
+        let parsed = parse_hint(ctx, &info)?;
+        assert_eq!(parsed.required_fee, Some(100));
+        assert_eq!(parsed.extra_conditions, info.extra_conditions);
+
+        // ...but reject the ones they can't reproduce
+        for inner_conditions in [
+            // different order
+            Conditions::new()
+                .reserve_fee(100)
+                .assert_before_seconds_absolute(1_000_000),
+            // duplicate fee
+            Conditions::new().reserve_fee(100).reserve_fee(100),
+        ] {
+            let mut hint = info.to_hint();
+            hint.inner_puzzle = clvm_quote!(inner_conditions);
+            assert!(PartialOfferInfo::from_hint(&hint).is_none());
+        }
+        let mut hint = info.to_hint();
+        hint.inner_puzzle.0 = 2;
+        assert!(PartialOfferInfo::from_hint(&hint).is_none());
+
+        // conditions that can't be serialized don't get a puzzle hash
+        let info = info
+            .with_extra_conditions(Conditions::new().remark(Program::from(Bytes::new(vec![0xff]))));
+        assert!(info.puzzle_hash().is_err());
+        assert!(PartialOffer::new(Bytes32::default(), 1_000, info).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_start_time_and_height_expiration() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let price_data = PartialPriceData {
+            price_precision: 1,
+            precision: 1,
+        };
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            Bytes32::new([2; 32]),
+            Some(10_000),
+            None,
+            price_data,
+            0,
+        )
+        .with_start_time(Some(100))
+        .with_expiration_height(Some(1_000_000));
+        let partial_offer = sim_partial_offer(&mut sim, info.clone(), 1_000)?;
+
+        // pre-published offers keep their price, but can't be filled yet
+        assert!(!info.is_started(99));
+        assert_eq!(info.price_data_at(0), price_data);
+        assert!(fill_partial_offer(ctx, &mut sim, &partial_offer, 400, 400, 99, None).is_err());
+
+        let (coin_spends, reconstructed) =
+            fill_partial_offer(ctx, &mut sim, &partial_offer, 400, 400, 100, None)?;
+        assert_eq!(reconstructed.offer.info.start_time, Some(100));
+        assert_eq!(reconstructed.offer.info.expiration_height, Some(1_000_000));
+        assert_eq!(reconstructed.offer.info.extra_conditions, Conditions::new());
+
+        // ASSERT_SECONDS_ABSOLUTE only passes once the start time is reached
+        assert!(sim.spend_coins(coin_spends.clone(), &[]).is_err());
+        sim.pass_time(100);
+        sim.spend_coins(coin_spends, &[])?;
+        assert!(
+            sim.coin_state(partial_offer.child(600)?.coin.coin_id())
+                .is_some()
+        );
+
+        // hints carry start times and heights as regular inner conditions
+        let info = info.with_start_height(Some(50));
+        assert!(!info.is_started_at_height(49));
+        assert!(info.is_started_at_height(50));
+        assert!(!info.is_expired_at_height(999_999));
+        assert!(info.is_expired_at_height(1_000_000));
+
+        let parsed = parse_hint(ctx, &info)?;
+        assert_eq!(parsed.start_time, Some(100));
+        assert_eq!(parsed.start_height, Some(50));
+        assert_eq!(parsed.expiration, Some(10_000));
+        assert_eq!(parsed.expiration_height, Some(1_000_000));
+        assert_eq!(parsed.extra_conditions, Conditions::new());
+
+        // auction steps can't be used before the start time either
+        let dutch_info = info.with_dutch_auction(Some(PartialDutchAuction {
+            start_time: 0,
+            end_time: 1_000,
+            end_precision: 2,
+            steps: 11,
+        }));
+        let steps = dutch_info.steps();
+        assert_eq!(steps[0].start_time, Some(100));
+        assert_eq!(steps[1].start_time, Some(100));
+        assert_eq!(steps[2].start_time, Some(200));
+        assert_eq!(dutch_info.active_step(150), Some(steps[1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fill_authorizer() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let authorizer_bls = sim.bls(0);
+        let stranger_bls = sim.bls(0);
+        let agg_sig_me_additional_data = get_constants(true).agg_sig_me_additional_data;
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::xch(),
+            Bytes32::new([2; 32]),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        )
+        .with_fill_authorizer(Some(authorizer_bls.pk));
+        let partial_offer = sim_partial_offer(&mut sim, info.clone(), 1_000)?;
+        let coin = partial_offer.coin;
+
+        let create_coin = |amount| CreateCoin::<Memos> {
+            puzzle_hash: TAKER_PUZZLE_HASH,
+            amount,
+            memos: Memos::None,
+        };
+        // the delegated puzzle creates the taker's coin
+        assert!(
+            partial_offer
+                .partial_coin_spend::<Memos>(ctx, 400, None, 0, None)
+                .is_err()
+        );
+
+        let authorizer = LocalFillAuthorizer::new(authorizer_bls.sk, agg_sig_me_additional_data);
+        let authorization = partial_offer
+            .fill_authorization(ctx, 400, &create_coin(400))?
+            .unwrap();
+        assert_eq!(authorization.coin_id, coin.coin_id());
+        assert_eq!(authorization.offered_amount, 400);
+        assert!(
+            LocalFillAuthorizer::new(stranger_bls.sk, agg_sig_me_additional_data)
+                .authorize(&authorization)
+                .is_err()
+        );
+        let signature = authorizer.authorize(&authorization)?;
+        // signatures are only valid for the fill they were made for
+        let other_signature = authorizer.authorize(
+            &partial_offer
+                .fill_authorization(ctx, 400, &create_coin(500))?
+                .unwrap(),
+        )?;
+
+        let (coin_spends, reconstructed) =
+            fill_partial_offer(ctx, &mut sim, &partial_offer, 400, 400, 0, None)?;
+        assert_eq!(
+            reconstructed.offer.info.fill_authorizer,
+            Some(authorizer_bls.pk)
+        );
+        assert_eq!(reconstructed.fill.offered_amount, 400);
+
+        for bad_signature in [Signature::default(), other_signature] {
+            assert!(
+                sim.new_transaction(SpendBundle::new(coin_spends.clone(), bad_signature))
+                    .is_err()
+            );
+        }
+        sim.new_transaction(SpendBundle::new(coin_spends, signature))?;
+        assert!(
+            sim.coin_state(partial_offer.child(600)?.coin.coin_id())
+                .is_some()
+        );
+        assert!(
+            sim.coin_state(Coin::new(coin.coin_id(), TAKER_PUZZLE_HASH, 400).coin_id())
+                .is_some()
+        );
+
+        // hints carry the authorizer key
+        assert_eq!(
+            parse_hint(ctx, &info)?.fill_authorizer,
+            Some(authorizer_bls.pk)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bonding_curve_fills() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let maker_puzzle_hash = Bytes32::new([2; 32]);
+        for curve in [
+            PartialCurve::linear(10_000, 10_000, 10_000, 30_000),
+            PartialCurve::constant_product(10_000 * 20_000),
+        ] {
+            let info = PartialOfferInfo::new(
+                None,
+                PartialOfferAssetInfo::xch(),
+                PartialOfferAssetInfo::xch(),
+                maker_puzzle_hash,
+                None,
+                None,
+                curve.spot_price(10_000),
+                0,
+            )
+            .with_curve(Some(curve));
+            assert_eq!(info.leaves()?.len(), 2);
+
+            let mut partial_offer = sim_partial_offer(&mut sim, info, 10_000)?;
+            let mut last_price = 0.0;
+            for _ in 0..2 {
+                let quote = partial_offer.quote(4_000, 0)?;
+                assert!(quote.output > 0);
+
+                // price goes up as the coin is drained
+                let price = quote.input as f64 / quote.output as f64;
+                assert!(price > last_price);
+                last_price = price;
+
+                let (coin_spends, reconstructed) = fill_partial_offer(
+                    ctx,
+                    &mut sim,
+                    &partial_offer,
+                    quote.input,
+                    quote.output,
+                    0,
+                    None,
+                )?;
+                let fill = partial_offer.parse_fill(ctx, &coin_spends[0])?.unwrap();
+                assert_eq!(fill.fill.offered_amount, quote.output);
+                assert_eq!(reconstructed.offer.info.curve, Some(curve));
+
+                sim.spend_coins(coin_spends, &[])?;
+
+                let child = fill.child.unwrap();
+                assert!(sim.coin_state(child.coin.coin_id()).is_some());
+                partial_offer = child;
+            }
+        }
+
+        Ok(())
+    }
+
     #[rstest]
     #[case("XCH for CAT", false, false, true, false)]
     #[case("XCH for rCAT", false, false, true, true)]
@@ -640,7 +2021,7 @@ mod tests {
                     0,
                 );
                 let partial_creation_conds = Conditions::new().create_coin(
-                    partial_offer_info.inner_puzzle_hash().into(),
+                    partial_offer_info.inner_puzzle_hash()?.into(),
                     offered_amount,
                     Memos::None,
                 );
@@ -665,7 +2046,7 @@ mod tests {
                 };
 
                 let mut partial_offer =
-                    PartialOffer::new(partial_offer_parent_id, offered_amount, partial_offer_info);
+                    PartialOffer::new(partial_offer_parent_id, offered_amount, partial_offer_info)?;
                 sim.spend_coins(ctx.take(), &[taker_bls.sk.clone(), maker_bls.sk.clone()])?;
 
                 // Accept partial offer
@@ -676,7 +2057,7 @@ mod tests {
                         // fill_no = 1 -> we're filling the rest of the offer
                         asked_amount * 3 / 4
                     };
-                    let expected_amount = PartialOffer::quote(given_amount, price_data);
+                    let expected_amount = price_data.quote(given_amount)?.output;
                     if partial_fill_only {
                         assert_eq!(expected_amount, offered_amount / 4);
                     } else {
@@ -744,8 +2125,10 @@ mod tests {
                         asset_info,
                     );
 
-                    let new_partial_offer = partial_offer.child(offered_amount - expected_amount);
-                    let spend_bundle = partial_offer.accept_offer(ctx, offer)?;
+                    let new_partial_offer =
+                        partial_offer.child(offered_amount - expected_amount)?;
+                    let spend_bundle =
+                        partial_offer.accept_offer(ctx, offer, 0, None, &[], None)?;
                     benchmark.add_spends(
                         ctx,
                         &mut sim,
@@ -1,31 +1,69 @@
 use chia::{
-    clvm_utils::{ToTreeHash, TreeHash},
-    protocol::Bytes32,
+    bls::PublicKey,
+    clvm_utils::{ToTreeHash, TreeHash, tree_hash},
+    protocol::{Bytes32, Program},
 };
 use chia_puzzle_types::{LineageProof, cat::CatArgs};
 use chia_puzzles::SETTLEMENT_PAYMENT_HASH;
 use chia_wallet_sdk::{
     driver::{CatMaker, DriverError, SpendContext},
     types::{
-        Condition, Conditions, MerkleTree, Mod,
+        Conditions, MerkleTree, Mod,
         puzzles::{P2OneOfManyArgs, RevocationArgs},
     },
 };
-use clvm_traits::{clvm_list, clvm_quote};
-use clvmr::NodePtr;
+use clvm_traits::{ToClvm, clvm_quote};
+use clvmr::{Allocator, NodePtr};
 
-use crate::{PartialOfferAssetInfo, PartialOfferHint, PartialPriceData, PartialPuzzleArgs};
+use crate::{
+    ASSERT_SECONDS_ABSOLUTE_OPCODE, ASSERT_SECONDS_RELATIVE_OPCODE, FillAuthorizerArgs,
+    MAX_DUTCH_STEPS, PartialCurve, PartialCurvePuzzleArgs, PartialDutchAuction,
+    PartialFeeRecipient, PartialFeeSplitPuzzleArgs, PartialOfferAssetInfo, PartialOfferHint,
+    PartialPriceData, PartialPuzzleArgs, PartialTaker, PartialTakerAllowlist, ReclaimArgs,
+    TakerAllowlistArgs, TimelockArgs,
+};
 
 #[derive(Debug, Clone)]
 pub struct PartialOfferInfo {
     pub lineage_proof: Option<LineageProof>,
     pub offered_asset_info: PartialOfferAssetInfo,
     pub requested_asset_info: PartialOfferAssetInfo,
-    pub maker_puzzle_hash: Bytes32,
+    // fill proceeds go here
+    pub receiver_puzzle_hash: Bytes32,
+    // clawback leaf - can cancel, top up or reprice the offer
+    pub clawback_puzzle_hash: Bytes32,
     pub expiration: Option<u64>,
+    // ASSERT_BEFORE_HEIGHT_ABSOLUTE - can be combined with `expiration`
+    pub expiration_height: Option<u32>,
+    // fills are only valid from this timestamp (ASSERT_SECONDS_ABSOLUTE) and
+    //  height (ASSERT_HEIGHT_ABSOLUTE) on - e.g., for launches
+    pub start_time: Option<u64>,
+    pub start_height: Option<u32>,
     pub required_fee: Option<u64>,
     pub price_data: PartialPriceData,
     pub min_other_asset_amount_minus_one: u64,
+    pub dutch_auction: Option<PartialDutchAuction>,
+    // bonding-curve offers use the curve instead of price data (which is
+    //  only kept as the initial price)
+    pub curve: Option<PartialCurve>,
+    // expired offers can be sent back to the maker by anyone
+    pub reclaimable: bool,
+    // additional clawback leaves (e.g., a backup key or a time-locked
+    //  recovery puzzle - see `recovery_leaf`)
+    pub extra_leaves: Vec<Bytes32>,
+    // committed liquidity - no clawback leaf can be used before this timestamp
+    pub clawback_lock: Option<u64>,
+    // share of each fill's proceeds paid to third parties (e.g., frontends)
+    // not supported by bonding curves
+    pub fee_recipients: Vec<PartialFeeRecipient>,
+    // private (OTC) offers can only be filled by these takers
+    pub taker_allowlist: Option<PartialTakerAllowlist>,
+    // co-signed offers - every fill needs this key's signature
+    pub fill_authorizer: Option<PublicKey>,
+    // any other inner conditions (e.g., remarks or announcements), asserted
+    //  by every step after the ones above - conditions that have their own
+    //  field shouldn't be repeated here
+    pub extra_conditions: Conditions<Program>,
 }
 
 impl PartialOfferInfo {
@@ -44,11 +82,112 @@ impl PartialOfferInfo {
             lineage_proof,
             offered_asset_info,
             requested_asset_info,
-            maker_puzzle_hash,
+            receiver_puzzle_hash: maker_puzzle_hash,
+            clawback_puzzle_hash: maker_puzzle_hash,
             expiration,
+            expiration_height: None,
+            start_time: None,
+            start_height: None,
             required_fee,
             price_data,
             min_other_asset_amount_minus_one,
+            dutch_auction: None,
+            curve: None,
+            reclaimable: false,
+            extra_leaves: Vec::new(),
+            clawback_lock: None,
+            fee_recipients: Vec::new(),
+            taker_allowlist: None,
+            fill_authorizer: None,
+            extra_conditions: Conditions::new(),
+        }
+    }
+
+    pub fn with_dutch_auction(self, dutch_auction: Option<PartialDutchAuction>) -> Self {
+        Self {
+            dutch_auction,
+            ..self
+        }
+    }
+
+    pub fn with_curve(self, curve: Option<PartialCurve>) -> Self {
+        Self { curve, ..self }
+    }
+
+    // Proceeds go to `receiver_puzzle_hash` instead of the clawback puzzle hash
+    pub fn with_receiver_puzzle_hash(self, receiver_puzzle_hash: Bytes32) -> Self {
+        Self {
+            receiver_puzzle_hash,
+            ..self
+        }
+    }
+
+    // The reclaim leaf asserts the (seconds) expiration, so offers without one
+    //  can't be reclaimable
+    pub fn with_reclaimable(self, reclaimable: bool) -> Self {
+        Self {
+            reclaimable: reclaimable && self.expiration.is_some(),
+            ..self
+        }
+    }
+
+    pub fn with_extra_leaves(self, extra_leaves: Vec<Bytes32>) -> Self {
+        Self {
+            extra_leaves,
+            ..self
+        }
+    }
+
+    pub fn with_clawback_lock(self, clawback_lock: Option<u64>) -> Self {
+        Self {
+            clawback_lock,
+            ..self
+        }
+    }
+
+    pub fn with_fee_recipients(self, fee_recipients: Vec<PartialFeeRecipient>) -> Self {
+        Self {
+            fee_recipients,
+            ..self
+        }
+    }
+
+    pub fn with_taker_allowlist(self, taker_allowlist: Option<PartialTakerAllowlist>) -> Self {
+        Self {
+            taker_allowlist,
+            ..self
+        }
+    }
+
+    pub fn with_fill_authorizer(self, fill_authorizer: Option<PublicKey>) -> Self {
+        Self {
+            fill_authorizer,
+            ..self
+        }
+    }
+
+    pub fn with_expiration_height(self, expiration_height: Option<u32>) -> Self {
+        Self {
+            expiration_height,
+            ..self
+        }
+    }
+
+    pub fn with_start_time(self, start_time: Option<u64>) -> Self {
+        Self { start_time, ..self }
+    }
+
+    pub fn with_start_height(self, start_height: Option<u32>) -> Self {
+        Self {
+            start_height,
+            ..self
+        }
+    }
+
+    pub fn with_extra_conditions(self, extra_conditions: Conditions<Program>) -> Self {
+        Self {
+            extra_conditions,
+            ..self
         }
     }
 
@@ -117,10 +256,97 @@ impl PartialOfferInfo {
         }
     }
 
-    pub fn to_args(
+    // Steps of the offer, each being a separate partial puzzle leaf
+    // Fixed-price and bonding-curve offers have a single step that is valid
+    //  from the start time until expiration
+    // Dutch auctions get one fixed-price step per time window; the price only
+    //  changes at step boundaries (more steps = smoother decay, bigger tree)
+    pub fn steps(&self) -> Vec<PartialOfferStep> {
+        let Some(auction) = self.dutch_auction.filter(|_| self.curve.is_none()) else {
+            return vec![PartialOfferStep {
+                price_data: self.price_data,
+                start_time: self.start_time,
+                end_time: self.expiration,
+            }];
+        };
+
+        let steps = auction.steps.clamp(2, MAX_DUTCH_STEPS);
+        let boundary = |i: u64| {
+            auction.start_time
+                + (auction.end_time.saturating_sub(auction.start_time) as u128 * i as u128
+                    / (steps - 1) as u128) as u64
+        };
+
+        (0..steps)
+            .map(|i| {
+                let start_precision = self.price_data.precision as i128;
+                let precision = start_precision
+                    + (auction.end_precision as i128 - start_precision) * i as i128
+                        / (steps - 1) as i128;
+
+                PartialOfferStep {
+                    price_data: PartialPriceData {
+                        price_precision: self.price_data.price_precision,
+                        precision: precision as u64,
+                    },
+                    // no step can be used before the offer's start time
+                    start_time: if i > 0 {
+                        Some(boundary(i).max(self.start_time.unwrap_or(0)))
+                    } else {
+                        self.start_time
+                    },
+                    end_time: if i < steps - 1 {
+                        Some(boundary(i + 1))
+                    } else {
+                        self.expiration
+                    },
+                }
+            })
+            .collect()
+    }
+
+    // Step that can be used for fills at `now`, if any
+    pub fn active_step(&self, now: u64) -> Option<PartialOfferStep> {
+        self.steps().into_iter().find(|step| step.is_active(now))
+    }
+
+    // Falls back to the first step's price before the offer starts and to the
+    //  last step's price once no step is active
+    pub fn price_data_at(&self, now: u64) -> PartialPriceData {
+        let mut steps = self.steps();
+        self.active_step(now)
+            .or_else(|| {
+                if self.is_started(now) {
+                    steps.pop()
+                } else {
+                    steps.first().copied()
+                }
+            })
+            .map_or(self.price_data, |step| step.price_data)
+    }
+
+    // ASSERT_SECONDS_ABSOLUTE passes once the timestamp is reached
+    pub fn is_started(&self, now: u64) -> bool {
+        self.start_time.is_none_or(|start_time| now >= start_time)
+    }
+
+    // `height` is the height of the block that would include the spend
+    pub fn is_started_at_height(&self, height: u32) -> bool {
+        self.start_height
+            .is_none_or(|start_height| height >= start_height)
+    }
+
+    // ASSERT_BEFORE_HEIGHT_ABSOLUTE fails once the height is reached
+    pub fn is_expired_at_height(&self, height: u32) -> bool {
+        self.expiration_height
+            .is_some_and(|expiration_height| height >= expiration_height)
+    }
+
+    pub fn step_args(
         &self,
         ctx: &mut SpendContext,
-    ) -> Result<PartialPuzzleArgs<NodePtr, (i8, Conditions)>, DriverError> {
+        step: &PartialOfferStep,
+    ) -> Result<PartialPuzzleArgs<NodePtr, NodePtr>, DriverError> {
         let offered_cat_maker = Self::to_cat_maker(self.offered_asset_info);
 
         let other_asset_offer_mod =
@@ -129,100 +355,422 @@ impl PartialOfferInfo {
         Ok(PartialPuzzleArgs {
             cat_maker: offered_cat_maker.get_puzzle(ctx)?,
             other_asset_offer_mod,
-            receiver_puzzle_hash: self.maker_puzzle_hash,
-            inner_puzzle: self.quoted_inner_conditions(),
-            price_data: self.price_data,
+            receiver_puzzle_hash: self.receiver_puzzle_hash,
+            inner_puzzle: self.step_inner_puzzle(ctx, step)?,
+            price_data: step.price_data,
+            min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
+        })
+    }
+
+    pub fn fee_split_args(
+        &self,
+        ctx: &mut SpendContext,
+        step: &PartialOfferStep,
+    ) -> Result<PartialFeeSplitPuzzleArgs<NodePtr, NodePtr>, DriverError> {
+        let offered_cat_maker = Self::to_cat_maker(self.offered_asset_info);
+
+        let other_asset_offer_mod =
+            Self::full_asset_puzzle_hash(self.requested_asset_info, SETTLEMENT_PAYMENT_HASH.into());
+
+        Ok(PartialFeeSplitPuzzleArgs {
+            cat_maker: offered_cat_maker.get_puzzle(ctx)?,
+            other_asset_offer_mod,
+            receiver_puzzle_hash: self.receiver_puzzle_hash,
+            fee_recipients: self.fee_recipients.clone(),
+            inner_puzzle: self.step_inner_puzzle(ctx, step)?,
+            min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
+            price_data: step.price_data,
+        })
+    }
+
+    // Part of `other_asset_amount` that goes to fee recipients
+    pub fn total_fees(&self, other_asset_amount: u64) -> u64 {
+        self.fee_recipients
+            .iter()
+            .map(|recipient| recipient.fee(other_asset_amount))
+            .fold(0, u64::saturating_add)
+    }
+
+    pub fn curve_args(
+        &self,
+        ctx: &mut SpendContext,
+        step: &PartialOfferStep,
+        curve: &PartialCurve,
+    ) -> Result<PartialCurvePuzzleArgs<NodePtr, NodePtr, NodePtr>, DriverError> {
+        let offered_cat_maker = Self::to_cat_maker(self.offered_asset_info);
+
+        let other_asset_offer_mod =
+            Self::full_asset_puzzle_hash(self.requested_asset_info, SETTLEMENT_PAYMENT_HASH.into());
+
+        Ok(PartialCurvePuzzleArgs {
+            cat_maker: offered_cat_maker.get_puzzle(ctx)?,
+            other_asset_offer_mod,
+            receiver_puzzle_hash: self.receiver_puzzle_hash,
+            inner_puzzle: self.step_inner_puzzle(ctx, step)?,
             min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
+            curve: curve.curve_puzzle(ctx)?,
         })
     }
 
-    pub fn quoted_inner_conditions(&self) -> (i8, Conditions) {
+    // Conditions shared by all steps (these are the ones stored in the hint)
+    // The order is canonical - `from_hint` only accepts hints that use it
+    pub fn quoted_inner_conditions(&self) -> (i8, Conditions<Program>) {
         let mut inner_conditions = Conditions::new();
+        if let Some(start_time) = self.start_time {
+            inner_conditions = inner_conditions.assert_seconds_absolute(start_time);
+        }
         if let Some(expiration) = self.expiration {
             inner_conditions = inner_conditions.assert_before_seconds_absolute(expiration);
         }
+
+        clvm_quote!(self.common_inner_conditions(inner_conditions))
+    }
+
+    // Conditions that come after the time-based ones, in canonical order
+    fn common_inner_conditions(
+        &self,
+        mut inner_conditions: Conditions<Program>,
+    ) -> Conditions<Program> {
+        if let Some(start_height) = self.start_height {
+            inner_conditions = inner_conditions.assert_height_absolute(start_height);
+        }
+        if let Some(expiration_height) = self.expiration_height {
+            inner_conditions = inner_conditions.assert_before_height_absolute(expiration_height);
+        }
         if let Some(required_fee) = self.required_fee {
             inner_conditions = inner_conditions.reserve_fee(required_fee);
         }
 
-        clvm_quote!(inner_conditions)
-    }
-
-    pub fn partial_puzzle_hash(&self) -> TreeHash {
-        PartialPuzzleArgs {
-            cat_maker: Self::to_cat_maker(self.offered_asset_info).curry_tree_hash(),
-            other_asset_offer_mod: Self::full_asset_puzzle_hash(
-                self.requested_asset_info,
-                SETTLEMENT_PAYMENT_HASH.into(),
-            ),
-            receiver_puzzle_hash: self.maker_puzzle_hash,
-            // forbidden hack
-            inner_puzzle: match (self.expiration, self.required_fee) {
-                (Some(expiration), Some(required_fee)) => clvm_quote!(clvm_list!(
-                    clvm_list!(85, expiration),
-                    clvm_list!(52, required_fee)
-                ))
-                .tree_hash(),
-                (Some(expiration), None) => {
-                    clvm_quote!(clvm_list!(clvm_list!(85, expiration))).tree_hash()
-                }
-                (None, Some(required_fee)) => {
-                    clvm_quote!(clvm_list!(clvm_list!(52, required_fee))).tree_hash()
-                }
-                (None, None) => clvm_quote!(()).tree_hash(),
-            },
-            price_data: self.price_data,
+        inner_conditions.extend(self.extra_conditions.iter().cloned())
+    }
+
+    pub fn step_inner_conditions(&self, step: &PartialOfferStep) -> (i8, Conditions<Program>) {
+        let mut inner_conditions = Conditions::new();
+        if let Some(start_time) = step.start_time {
+            inner_conditions = inner_conditions.assert_seconds_absolute(start_time);
+        }
+        if let Some(end_time) = step.end_time {
+            inner_conditions = inner_conditions.assert_before_seconds_absolute(end_time);
+        }
+
+        clvm_quote!(self.common_inner_conditions(inner_conditions))
+    }
+
+    // Quoted step conditions, wrapped in the taker allowlist for private offers
+    //  and in the fill authorizer for co-signed ones
+    pub fn step_inner_puzzle(
+        &self,
+        ctx: &mut SpendContext,
+        step: &PartialOfferStep,
+    ) -> Result<NodePtr, DriverError> {
+        let inner_conditions = self.step_inner_conditions(step);
+
+        let inner_puzzle = if let Some(taker_allowlist) = &self.taker_allowlist {
+            ctx.curry(TakerAllowlistArgs {
+                allowed_keys: taker_allowlist.keys.clone(),
+                allowed_puzzle_hashes: taker_allowlist.puzzle_hashes.clone(),
+                inner_puzzle: inner_conditions,
+            })?
+        } else {
+            ctx.alloc(&inner_conditions)?
+        };
+
+        if let Some(authorizer_key) = self.fill_authorizer {
+            ctx.curry(FillAuthorizerArgs {
+                authorizer_key,
+                inner_puzzle,
+            })
+        } else {
+            Ok(inner_puzzle)
+        }
+    }
+
+    pub fn step_inner_puzzle_hash(&self, step: &PartialOfferStep) -> Result<TreeHash, DriverError> {
+        // inner conditions are hashed in a throwaway allocator
+        let mut allocator = Allocator::new();
+        let inner_conditions = self.step_inner_conditions(step).to_clvm(&mut allocator)?;
+        let inner_conditions = tree_hash(&allocator, inner_conditions);
+
+        let inner_puzzle = if let Some(taker_allowlist) = &self.taker_allowlist {
+            TakerAllowlistArgs {
+                allowed_keys: taker_allowlist.keys.clone(),
+                allowed_puzzle_hashes: taker_allowlist.puzzle_hashes.clone(),
+                inner_puzzle: inner_conditions,
+            }
+            .curry_tree_hash()
+        } else {
+            inner_conditions
+        };
+
+        Ok(if let Some(authorizer_key) = self.fill_authorizer {
+            FillAuthorizerArgs {
+                authorizer_key,
+                inner_puzzle,
+            }
+            .curry_tree_hash()
+        } else {
+            inner_puzzle
+        })
+    }
+
+    pub fn is_taker_allowed(&self, taker: &PartialTaker) -> bool {
+        self.taker_allowlist
+            .as_ref()
+            .is_none_or(|taker_allowlist| taker_allowlist.allows(taker))
+    }
+
+    pub fn step_puzzle_hash(&self, step: &PartialOfferStep) -> Result<TreeHash, DriverError> {
+        let cat_maker = Self::to_cat_maker(self.offered_asset_info).curry_tree_hash();
+        let other_asset_offer_mod =
+            Self::full_asset_puzzle_hash(self.requested_asset_info, SETTLEMENT_PAYMENT_HASH.into());
+        let inner_puzzle = self.step_inner_puzzle_hash(step)?;
+
+        if let Some(curve) = self.curve {
+            return Ok(PartialCurvePuzzleArgs {
+                cat_maker,
+                other_asset_offer_mod,
+                receiver_puzzle_hash: self.receiver_puzzle_hash,
+                inner_puzzle,
+                min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
+                curve: curve.curve_puzzle_hash(),
+            }
+            .curry_tree_hash());
+        }
+
+        if !self.fee_recipients.is_empty() {
+            return Ok(PartialFeeSplitPuzzleArgs {
+                cat_maker,
+                other_asset_offer_mod,
+                receiver_puzzle_hash: self.receiver_puzzle_hash,
+                fee_recipients: self.fee_recipients.clone(),
+                inner_puzzle,
+                min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
+                price_data: step.price_data,
+            }
+            .curry_tree_hash());
+        }
+
+        Ok(PartialPuzzleArgs {
+            cat_maker,
+            other_asset_offer_mod,
+            receiver_puzzle_hash: self.receiver_puzzle_hash,
+            inner_puzzle,
+            price_data: step.price_data,
             min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
         }
+        .curry_tree_hash())
+    }
+
+    pub fn reclaim_args(&self) -> Option<ReclaimArgs> {
+        if !self.reclaimable {
+            return None;
+        }
+
+        self.expiration.map(|expiration| ReclaimArgs {
+            maker_puzzle_hash: self.clawback_puzzle_hash,
+            expiration,
+        })
+    }
+
+    pub fn reclaim_puzzle_hash(&self) -> Option<TreeHash> {
+        self.reclaim_args().map(|args| args.curry_tree_hash())
+    }
+
+    // Leaf that lets `puzzle_hash` claw back once the partial coin is at
+    //  least `delay` seconds old (fills recreate the coin, resetting the timer)
+    pub fn recovery_leaf(delay: u64, puzzle_hash: Bytes32) -> Bytes32 {
+        TimelockArgs {
+            condition_opcode: ASSERT_SECONDS_RELATIVE_OPCODE,
+            seconds: delay,
+            inner_puzzle: TreeHash::from(puzzle_hash),
+        }
         .curry_tree_hash()
+        .into()
     }
 
-    pub fn inner_puzzle_hash(&self) -> TreeHash {
-        P2OneOfManyArgs::new(
-            MerkleTree::new(&[self.partial_puzzle_hash().into(), self.maker_puzzle_hash]).root(),
-        )
+    // Merkle leaf for a clawback puzzle (the clawback puzzle hash or one of
+    //  the extra leaves) - wrapped in an absolute timelock if the offer is locked
+    pub fn clawback_leaf(&self, puzzle_hash: Bytes32) -> Bytes32 {
+        let Some(clawback_lock) = self.clawback_lock else {
+            return puzzle_hash;
+        };
+
+        TimelockArgs {
+            condition_opcode: ASSERT_SECONDS_ABSOLUTE_OPCODE,
+            seconds: clawback_lock,
+            inner_puzzle: TreeHash::from(puzzle_hash),
+        }
         .curry_tree_hash()
+        .into()
+    }
+
+    pub fn is_clawback_locked(&self, now: u64) -> bool {
+        self.clawback_lock
+            .is_some_and(|clawback_lock| now < clawback_lock)
+    }
+
+    // All 1-of-n leaves: partial puzzle steps first, then the reclaim leaf
+    //  (if any) and extra leaves, clawback puzzle hash last
+    pub fn leaves(&self) -> Result<Vec<Bytes32>, DriverError> {
+        let mut leaves = self
+            .steps()
+            .iter()
+            .map(|step| Ok(self.step_puzzle_hash(step)?.into()))
+            .collect::<Result<Vec<_>, DriverError>>()?;
+        if let Some(reclaim_puzzle_hash) = self.reclaim_puzzle_hash() {
+            leaves.push(reclaim_puzzle_hash.into());
+        }
+        leaves.extend(
+            self.extra_leaves
+                .iter()
+                .map(|leaf| self.clawback_leaf(*leaf)),
+        );
+        leaves.push(self.clawback_leaf(self.clawback_puzzle_hash));
+        Ok(leaves)
+    }
+
+    pub fn merkle_tree(&self) -> Result<MerkleTree, DriverError> {
+        Ok(MerkleTree::new(&self.leaves()?))
     }
 
-    pub fn puzzle_hash(&self) -> Bytes32 {
-        Self::full_asset_puzzle_hash(self.offered_asset_info, self.inner_puzzle_hash().into())
+    pub fn inner_puzzle_hash(&self) -> Result<TreeHash, DriverError> {
+        Ok(P2OneOfManyArgs::new(self.merkle_tree()?.root()).curry_tree_hash())
     }
 
-    pub fn to_hint(&self) -> PartialOfferHint<(i8, Conditions)> {
+    pub fn puzzle_hash(&self) -> Result<Bytes32, DriverError> {
+        Ok(Self::full_asset_puzzle_hash(
+            self.offered_asset_info,
+            self.inner_puzzle_hash()?.into(),
+        ))
+    }
+
+    pub fn to_hint(&self) -> PartialOfferHint<(i8, Conditions<Program>)> {
         PartialOfferHint {
             lineage_proof: self.lineage_proof,
             offered_asset_info: self.offered_asset_info,
             requested_asset_info: self.requested_asset_info,
-            maker_puzzle_hash: self.maker_puzzle_hash,
+            clawback_puzzle_hash: self.clawback_puzzle_hash,
             inner_puzzle: self.quoted_inner_conditions(),
             price_data: self.price_data,
             min_other_asset_amount_minus_one: self.min_other_asset_amount_minus_one,
+            dutch_auction: self.dutch_auction,
+            linear_curve: match self.curve {
+                Some(PartialCurve::Linear(curve)) => Some(curve),
+                _ => None,
+            },
+            constant_product_curve: match self.curve {
+                Some(PartialCurve::ConstantProduct(curve)) => Some(curve),
+                _ => None,
+            },
+            reclaimable: self.reclaimable,
+            receiver_puzzle_hash: Some(self.receiver_puzzle_hash)
+                .filter(|receiver_puzzle_hash| *receiver_puzzle_hash != self.clawback_puzzle_hash),
+            extra_leaves: self.extra_leaves.clone(),
+            clawback_lock: self.clawback_lock,
+            fee_recipients: self.fee_recipients.clone(),
+            taker_allowlist: self.taker_allowlist.clone(),
+            fill_authorizer: self.fill_authorizer,
         }
     }
 
-    pub fn from_hint(hint: &PartialOfferHint<(i8, Conditions)>) -> Option<Self> {
-        let expiration = hint
-            .inner_puzzle
-            .1
-            .iter()
-            .find_map(Condition::as_assert_before_seconds_absolute)
-            .map(|cond| cond.seconds);
-        let required_fee = hint
-            .inner_puzzle
-            .1
-            .iter()
-            .find_map(Condition::as_reserve_fee)
-            .map(|cond| cond.amount);
+    // Returns None for hints that can't be described without losing
+    //  information (i.e., where `to_hint` would not give the same hint back)
+    pub fn from_hint(hint: &PartialOfferHint<(i8, Conditions<Program>)>) -> Option<Self> {
+        // conditions with their own field are pulled out, everything else is kept
+        let mut expiration = None;
+        let mut expiration_height = None;
+        let mut start_time = None;
+        let mut start_height = None;
+        let mut required_fee = None;
+        let mut extra_conditions = Conditions::new();
+        for condition in hint.inner_puzzle.1.iter() {
+            if let Some(cond) = condition.as_assert_before_seconds_absolute() {
+                if expiration.replace(cond.seconds).is_some() {
+                    return None;
+                }
+            } else if let Some(cond) = condition.as_assert_before_height_absolute() {
+                if expiration_height.replace(cond.height).is_some() {
+                    return None;
+                }
+            } else if let Some(cond) = condition.as_assert_seconds_absolute() {
+                if start_time.replace(cond.seconds).is_some() {
+                    return None;
+                }
+            } else if let Some(cond) = condition.as_assert_height_absolute() {
+                if start_height.replace(cond.height).is_some() {
+                    return None;
+                }
+            } else if let Some(cond) = condition.as_reserve_fee() {
+                if required_fee.replace(cond.amount).is_some() {
+                    return None;
+                }
+            } else {
+                extra_conditions = extra_conditions.with(condition.clone());
+            }
+        }
+        if hint
+            .dutch_auction
+            .is_some_and(|auction| auction.steps > MAX_DUTCH_STEPS)
+            || !PartialFeeRecipient::are_valid(&hint.fee_recipients)
+            || hint.reclaimable && expiration.is_none()
+        {
+            return None;
+        }
+        let curve = match (hint.linear_curve, hint.constant_product_curve) {
+            (Some(_), Some(_)) => return None,
+            (Some(_), _) | (_, Some(_)) if !hint.fee_recipients.is_empty() => return None,
+            (linear_curve, constant_product_curve) => linear_curve
+                .map(PartialCurve::Linear)
+                .or(constant_product_curve.map(PartialCurve::ConstantProduct)),
+        };
 
-        Some(Self {
+        let info = Self {
             lineage_proof: hint.lineage_proof,
             offered_asset_info: hint.offered_asset_info,
             requested_asset_info: hint.requested_asset_info,
-            maker_puzzle_hash: hint.maker_puzzle_hash,
+            receiver_puzzle_hash: hint
+                .receiver_puzzle_hash
+                .unwrap_or(hint.clawback_puzzle_hash),
+            clawback_puzzle_hash: hint.clawback_puzzle_hash,
             expiration,
+            expiration_height,
+            start_time,
+            start_height,
             required_fee,
             price_data: hint.price_data,
             min_other_asset_amount_minus_one: hint.min_other_asset_amount_minus_one,
-        })
+            dutch_auction: hint.dutch_auction,
+            curve,
+            reclaimable: hint.reclaimable,
+            extra_leaves: hint.extra_leaves.clone(),
+            clawback_lock: hint.clawback_lock,
+            fee_recipients: hint.fee_recipients.clone(),
+            taker_allowlist: hint.taker_allowlist.clone(),
+            fill_authorizer: hint.fill_authorizer,
+            extra_conditions,
+        };
+
+        // e.g., conditions in a different order or a puzzle that isn't quoted
+        if info.quoted_inner_conditions() != hint.inner_puzzle {
+            return None;
+        }
+
+        Some(info)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialOfferStep {
+    pub price_data: PartialPriceData,
+    // ASSERT_SECONDS_ABSOLUTE
+    pub start_time: Option<u64>,
+    // ASSERT_BEFORE_SECONDS_ABSOLUTE
+    pub end_time: Option<u64>,
+}
+
+impl PartialOfferStep {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.start_time.is_none_or(|start_time| now >= start_time)
+            && self.end_time.is_none_or(|end_time| now < end_time)
     }
 }
@@ -0,0 +1,513 @@
+use chia::{
+    bls::PublicKey,
+    protocol::{Bytes32, CoinSpend, Program},
+};
+use chia_puzzle_types::{
+    LineageProof,
+    cat::{CatArgs, CatSolution},
+};
+use chia_puzzles::{CAT_PUZZLE_HASH, SETTLEMENT_PAYMENT_HASH};
+use chia_wallet_sdk::{
+    driver::{DriverError, Puzzle, SpendContext},
+    types::{
+        Conditions, Mod,
+        puzzles::{P2OneOfManyArgs, P2OneOfManySolution, RevocationArgs, RevocationSolution},
+    },
+};
+use clvmr::NodePtr;
+
+use crate::{
+    ConstantProductCurveArgs, FillAuthorizerArgs, LinearCurveArgs, PartialCurve,
+    PartialCurvePuzzleArgs, PartialCurveSolution, PartialFeeRecipient, PartialFeeSplitPuzzleArgs,
+    PartialOffer, PartialOfferAssetInfo, PartialOfferHint, PartialOfferInfo, PartialPriceData,
+    PartialPuzzleArgs, PartialSolution, PartialTakerAllowlist, TakerAllowlistArgs,
+};
+
+#[derive(Debug, Clone)]
+pub struct PartialFill {
+    // amount of the requested asset paid to the maker
+    pub other_asset_amount: u64,
+    // amount of the offered asset released to the taker
+    pub offered_amount: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartialOfferSpend {
+    // the spent partial coin
+    pub offer: PartialOffer,
+    pub fill: PartialFill,
+    // recreated partial coin, if the fill left anything
+    pub child: Option<PartialOffer>,
+}
+
+// Partial puzzle step revealed by a fill, along with the layers around it
+struct PartialLeafSpend {
+    asset_id: Option<Bytes32>,
+    hidden_puzzle_hash: Option<Bytes32>,
+    lineage_proof: Option<LineageProof>,
+    inner_puzzle_hash: Bytes32,
+    other_asset_offer_mod: Bytes32,
+    receiver_puzzle_hash: Bytes32,
+    inner_puzzle: (i8, Conditions<Program>),
+    min_other_asset_amount_minus_one: u64,
+    // bonding curves use the price at the spent coin's amount
+    price_data: PartialPriceData,
+    curve: Option<PartialCurve>,
+    fee_recipients: Vec<PartialFeeRecipient>,
+    taker_allowlist: Option<PartialTakerAllowlist>,
+    fill_authorizer: Option<PublicKey>,
+    other_asset_amount: u64,
+    offered_amount: u64,
+}
+
+impl PartialOffer {
+    // Quoted inner conditions, unwrapping the fill authorizer of co-signed
+    //  offers and the taker allowlist of private ones
+    #[allow(clippy::type_complexity)]
+    fn parse_inner_puzzle(
+        ctx: &mut SpendContext,
+        inner_puzzle: NodePtr,
+    ) -> Result<
+        (
+            (i8, Conditions<Program>),
+            Option<PartialTakerAllowlist>,
+            Option<PublicKey>,
+        ),
+        DriverError,
+    > {
+        let mut inner_puzzle = inner_puzzle;
+        let mut fill_authorizer = None;
+        if let Some(curried) = Puzzle::parse(ctx, inner_puzzle).as_curried()
+            && curried.mod_hash == FillAuthorizerArgs::<NodePtr>::mod_hash()
+        {
+            let args = ctx.extract::<FillAuthorizerArgs<NodePtr>>(curried.args)?;
+
+            fill_authorizer = Some(args.authorizer_key);
+            inner_puzzle = args.inner_puzzle;
+        }
+
+        if let Some(curried) = Puzzle::parse(ctx, inner_puzzle).as_curried()
+            && curried.mod_hash == TakerAllowlistArgs::<NodePtr>::mod_hash()
+        {
+            let args =
+                ctx.extract::<TakerAllowlistArgs<(i8, Conditions<Program>)>>(curried.args)?;
+
+            return Ok((
+                args.inner_puzzle,
+                Some(PartialTakerAllowlist {
+                    keys: args.allowed_keys,
+                    puzzle_hashes: args.allowed_puzzle_hashes,
+                }),
+                fill_authorizer,
+            ));
+        }
+
+        Ok((ctx.extract(inner_puzzle)?, None, fill_authorizer))
+    }
+
+    fn parse_leaf_spend(
+        ctx: &mut SpendContext,
+        coin_spend: &CoinSpend,
+    ) -> Result<Option<PartialLeafSpend>, DriverError> {
+        let puzzle_ptr = ctx.alloc(&coin_spend.puzzle_reveal)?;
+        let solution_ptr = ctx.alloc(&coin_spend.solution)?;
+
+        let mut puzzle = Puzzle::parse(ctx, puzzle_ptr);
+        let mut solution = solution_ptr;
+
+        // CAT layer
+        let mut asset_id = None;
+        let mut lineage_proof: Option<LineageProof> = None;
+        if let Some(curried) = puzzle.as_curried()
+            && curried.mod_hash == CAT_PUZZLE_HASH.into()
+        {
+            let args = ctx.extract::<CatArgs<NodePtr>>(curried.args)?;
+            let cat_solution = ctx.extract::<CatSolution<NodePtr>>(solution)?;
+
+            asset_id = Some(args.asset_id);
+            lineage_proof = cat_solution.lineage_proof;
+            puzzle = Puzzle::parse(ctx, args.inner_puzzle);
+            solution = cat_solution.inner_puzzle_solution;
+        }
+
+        // revocation layer
+        let mut hidden_puzzle_hash = None;
+        if let Some(curried) = puzzle.as_curried()
+            && asset_id.is_some()
+            && curried.mod_hash == RevocationArgs::mod_hash()
+        {
+            let args = ctx.extract::<RevocationArgs>(curried.args)?;
+            let revocation_solution =
+                ctx.extract::<RevocationSolution<NodePtr, NodePtr>>(solution)?;
+            if revocation_solution.hidden {
+                return Ok(None);
+            }
+
+            hidden_puzzle_hash = Some(args.hidden_puzzle_hash);
+            puzzle = Puzzle::parse(ctx, revocation_solution.puzzle);
+            solution = revocation_solution.solution;
+        }
+
+        // 1-of-n layer
+        let Some(curried) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+        if curried.mod_hash != P2OneOfManyArgs::mod_hash() {
+            return Ok(None);
+        }
+        let inner_puzzle_hash: Bytes32 = ctx.tree_hash(puzzle.ptr()).into();
+        let p2_solution = ctx.extract::<P2OneOfManySolution<NodePtr, NodePtr>>(solution)?;
+
+        // partial puzzle (only revealed when the offer is filled)
+        let mut fee_recipients = Vec::new();
+        let Some(curried) = Puzzle::parse(ctx, p2_solution.puzzle).as_curried() else {
+            return Ok(None);
+        };
+        if curried.mod_hash == PartialCurvePuzzleArgs::<NodePtr, NodePtr, NodePtr>::mod_hash() {
+            let args =
+                ctx.extract::<PartialCurvePuzzleArgs<NodePtr, NodePtr, NodePtr>>(curried.args)?;
+            let (inner_puzzle, taker_allowlist, fill_authorizer) =
+                Self::parse_inner_puzzle(ctx, args.inner_puzzle)?;
+            let partial_solution = ctx
+                .extract::<PartialCurveSolution<NodePtr, NodePtr, NodePtr>>(p2_solution.solution)?;
+
+            let Some(curried_curve) = Puzzle::parse(ctx, args.curve).as_curried() else {
+                return Ok(None);
+            };
+            let curve = if curried_curve.mod_hash == LinearCurveArgs::mod_hash() {
+                let curve_args = ctx.extract::<LinearCurveArgs>(curried_curve.args)?;
+                PartialCurve::linear(
+                    curve_args.total_amount,
+                    curve_args.price_precision,
+                    curve_args.start_precision,
+                    curve_args.end_precision,
+                )
+            } else if curried_curve.mod_hash == ConstantProductCurveArgs::mod_hash() {
+                PartialCurve::constant_product(
+                    ctx.extract::<ConstantProductCurveArgs>(curried_curve.args)?
+                        .k,
+                )
+            } else {
+                return Ok(None);
+            };
+
+            return Ok(Some(PartialLeafSpend {
+                asset_id,
+                hidden_puzzle_hash,
+                lineage_proof,
+                inner_puzzle_hash,
+                other_asset_offer_mod: args.other_asset_offer_mod,
+                receiver_puzzle_hash: args.receiver_puzzle_hash,
+                inner_puzzle,
+                min_other_asset_amount_minus_one: args.min_other_asset_amount_minus_one,
+                price_data: curve.spot_price(coin_spend.coin.amount),
+                curve: Some(curve),
+                fee_recipients: Vec::new(),
+                taker_allowlist,
+                fill_authorizer,
+                other_asset_amount: partial_solution.other_asset_amount,
+                offered_amount: coin_spend
+                    .coin
+                    .amount
+                    .saturating_sub(partial_solution.new_amount),
+            }));
+        }
+        let args = if curried.mod_hash == PartialPuzzleArgs::<NodePtr, NodePtr>::mod_hash() {
+            ctx.extract::<PartialPuzzleArgs<NodePtr, NodePtr>>(curried.args)?
+        } else if curried.mod_hash == PartialFeeSplitPuzzleArgs::<NodePtr, NodePtr>::mod_hash() {
+            let args = ctx.extract::<PartialFeeSplitPuzzleArgs<NodePtr, NodePtr>>(curried.args)?;
+            fee_recipients = args.fee_recipients;
+
+            PartialPuzzleArgs {
+                cat_maker: args.cat_maker,
+                other_asset_offer_mod: args.other_asset_offer_mod,
+                receiver_puzzle_hash: args.receiver_puzzle_hash,
+                inner_puzzle: args.inner_puzzle,
+                min_other_asset_amount_minus_one: args.min_other_asset_amount_minus_one,
+                price_data: args.price_data,
+            }
+        } else {
+            return Ok(None);
+        };
+        let (inner_puzzle, taker_allowlist, fill_authorizer) =
+            Self::parse_inner_puzzle(ctx, args.inner_puzzle)?;
+        let partial_solution =
+            ctx.extract::<PartialSolution<NodePtr, NodePtr, NodePtr>>(p2_solution.solution)?;
+
+        // the revealed step determines the price that was used
+        let other_asset_amount = partial_solution.other_asset_amount;
+        let offered_amount = args.price_data.quote(other_asset_amount)?.output;
+
+        Ok(Some(PartialLeafSpend {
+            asset_id,
+            hidden_puzzle_hash,
+            lineage_proof,
+            inner_puzzle_hash,
+            other_asset_offer_mod: args.other_asset_offer_mod,
+            receiver_puzzle_hash: args.receiver_puzzle_hash,
+            inner_puzzle,
+            min_other_asset_amount_minus_one: args.min_other_asset_amount_minus_one,
+            price_data: args.price_data,
+            curve: None,
+            fee_recipients,
+            taker_allowlist,
+            fill_authorizer,
+            other_asset_amount,
+            offered_amount,
+        }))
+    }
+
+    fn spend_with_fill(
+        self,
+        leaf_spend: &PartialLeafSpend,
+    ) -> Result<PartialOfferSpend, DriverError> {
+        let other_asset_amount = leaf_spend.other_asset_amount;
+        let offered_amount = leaf_spend.offered_amount;
+        let child = if self.coin.amount > offered_amount {
+            Some(self.child(self.coin.amount - offered_amount)?)
+        } else {
+            None
+        };
+
+        Ok(PartialOfferSpend {
+            offer: self,
+            fill: PartialFill {
+                other_asset_amount,
+                offered_amount,
+            },
+            child,
+        })
+    }
+
+    // Parses the spend of a partial coin that was filled
+    // Returns None for coins that aren't partial offers and for clawback spends,
+    //  since the latter do not reveal the partial puzzle
+    // The requested asset can only be recovered from a puzzle hash, so CAT
+    //  candidates need to be provided (XCH is always checked) - spends
+    //  requesting any other asset are skipped
+    // Dutch auctions only reveal one of their steps, so they can't be
+    //  reconstructed this way - see `parse_fill` instead
+    // The same goes for offers with a clawback puzzle hash that differs from
+    //  the receiver, extra leaves or a clawback lock, since fills never reveal
+    //  those
+    pub fn from_parent_spend(
+        ctx: &mut SpendContext,
+        coin_spend: &CoinSpend,
+        requested_asset_candidates: &[PartialOfferAssetInfo],
+    ) -> Result<Option<PartialOfferSpend>, DriverError> {
+        let Some(leaf_spend) = Self::parse_leaf_spend(ctx, coin_spend)? else {
+            return Ok(None);
+        };
+
+        let offered_asset_info =
+            PartialOfferAssetInfo::new(leaf_spend.asset_id, leaf_spend.hidden_puzzle_hash);
+        let Some(requested_asset_info) = [PartialOfferAssetInfo::xch()]
+            .iter()
+            .chain(requested_asset_candidates)
+            .find(|asset_info| {
+                PartialOfferInfo::full_asset_puzzle_hash(
+                    **asset_info,
+                    SETTLEMENT_PAYMENT_HASH.into(),
+                ) == leaf_spend.other_asset_offer_mod
+            })
+            .copied()
+        else {
+            return Ok(None);
+        };
+
+        let Some(info) = PartialOfferInfo::from_hint(&PartialOfferHint {
+            lineage_proof: leaf_spend.lineage_proof,
+            offered_asset_info,
+            requested_asset_info,
+            price_data: leaf_spend.price_data,
+            clawback_puzzle_hash: leaf_spend.receiver_puzzle_hash,
+            inner_puzzle: leaf_spend.inner_puzzle.clone(),
+            min_other_asset_amount_minus_one: leaf_spend.min_other_asset_amount_minus_one,
+            dutch_auction: None,
+            linear_curve: match leaf_spend.curve {
+                Some(PartialCurve::Linear(curve)) => Some(curve),
+                _ => None,
+            },
+            constant_product_curve: match leaf_spend.curve {
+                Some(PartialCurve::ConstantProduct(curve)) => Some(curve),
+                _ => None,
+            },
+            reclaimable: false,
+            receiver_puzzle_hash: None,
+            extra_leaves: Vec::new(),
+            clawback_lock: None,
+            fee_recipients: leaf_spend.fee_recipients.clone(),
+            taker_allowlist: leaf_spend.taker_allowlist.clone(),
+            fill_authorizer: leaf_spend.fill_authorizer,
+        }) else {
+            return Err(DriverError::Custom(
+                "Partial offer has ambiguous inner conditions".to_string(),
+            ));
+        };
+
+        // fills don't reveal the reclaim leaf, so try both variants
+        let info = if info.expiration.is_some()
+            && info.inner_puzzle_hash()? != leaf_spend.inner_puzzle_hash.into()
+        {
+            info.with_reclaimable(true)
+        } else {
+            info
+        };
+
+        // make sure the reconstructed info describes the coin that was spent
+        if info.inner_puzzle_hash()? != leaf_spend.inner_puzzle_hash.into()
+            || info.puzzle_hash()? != coin_spend.coin.puzzle_hash
+        {
+            return Err(DriverError::Custom(
+                "Reconstructed partial offer does not match spent coin".to_string(),
+            ));
+        }
+
+        let offer = PartialOffer::new(
+            coin_spend.coin.parent_coin_info,
+            coin_spend.coin.amount,
+            info,
+        )?;
+        Ok(Some(offer.spend_with_fill(&leaf_spend)?))
+    }
+
+    // Same as `from_parent_spend`, but for a spend of this (known) offer's coin
+    pub fn parse_fill(
+        &self,
+        ctx: &mut SpendContext,
+        coin_spend: &CoinSpend,
+    ) -> Result<Option<PartialOfferSpend>, DriverError> {
+        if coin_spend.coin != self.coin {
+            return Err(DriverError::Custom(
+                "Spent coin is not the partial offer coin".to_string(),
+            ));
+        }
+
+        let Some(leaf_spend) = Self::parse_leaf_spend(ctx, coin_spend)? else {
+            return Ok(None);
+        };
+        if leaf_spend.inner_puzzle_hash != self.info.inner_puzzle_hash()?.into() {
+            return Err(DriverError::Custom(
+                "Spent coin does not match partial offer".to_string(),
+            ));
+        }
+
+        Ok(Some(self.clone().spend_with_fill(&leaf_spend)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzle_types::Memos;
+    use chia_wallet_sdk::{
+        driver::Spend,
+        prelude::{Coin, CreateCoin},
+        test::Simulator,
+    };
+    use clvm_traits::clvm_quote;
+
+    use crate::primitives::partial::tests::{fill_partial_offer, sim_partial_offer};
+
+    use super::*;
+
+    fn test_info(requested_asset_info: PartialOfferAssetInfo) -> PartialOfferInfo {
+        PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            requested_asset_info,
+            Bytes32::new([2; 32]),
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        )
+    }
+
+    #[test]
+    fn test_from_parent_spend_xch() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let info = test_info(PartialOfferAssetInfo::xch());
+        let partial_offer = sim_partial_offer(&mut sim, info.clone(), 1_000)?;
+        let (_, reconstructed) =
+            fill_partial_offer(ctx, &mut sim, &partial_offer, 400, 400, 0, None)?;
+
+        assert_eq!(reconstructed.offer.coin, partial_offer.coin);
+        assert_eq!(
+            reconstructed.offer.info.requested_asset_info,
+            info.requested_asset_info
+        );
+        assert_eq!(reconstructed.fill.offered_amount, 400);
+        assert_eq!(reconstructed.fill.other_asset_amount, 400);
+        assert_eq!(
+            reconstructed.child.map(|child| child.coin),
+            Some(partial_offer.child(600)?.coin)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_parent_spend_cat() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let requested_asset_info = PartialOfferAssetInfo::cat(Bytes32::new([4; 32]), None);
+        let partial_offer =
+            PartialOffer::new(Bytes32::default(), 1_000, test_info(requested_asset_info))?;
+        let (spend, _) = partial_offer.partial_coin_spend(
+            ctx,
+            400,
+            Some(CreateCoin::<Memos> {
+                puzzle_hash: Bytes32::new([3; 32]),
+                amount: 400,
+                memos: Memos::None,
+            }),
+            0,
+            None,
+        )?;
+        ctx.spend(partial_offer.coin, spend)?;
+        let coin_spend = ctx.take().remove(0);
+
+        // CATs are only recognized if they're one of the candidates
+        assert!(PartialOffer::from_parent_spend(ctx, &coin_spend, &[])?.is_none());
+        let other_cat = PartialOfferAssetInfo::cat(Bytes32::new([5; 32]), None);
+        assert!(PartialOffer::from_parent_spend(ctx, &coin_spend, &[other_cat])?.is_none());
+
+        let reconstructed =
+            PartialOffer::from_parent_spend(ctx, &coin_spend, &[other_cat, requested_asset_info])?
+                .unwrap();
+        assert_eq!(
+            reconstructed.offer.info.requested_asset_info,
+            requested_asset_info
+        );
+        assert_eq!(reconstructed.offer.coin, partial_offer.coin);
+        assert_eq!(reconstructed.fill.offered_amount, 400);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_parent_spend_not_partial() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let puzzle = ctx.alloc(&clvm_quote!(Conditions::new().create_coin(
+            Bytes32::new([3; 32]),
+            1_000,
+            Memos::None
+        )))?;
+        let coin = Coin::new(Bytes32::default(), ctx.tree_hash(puzzle).into(), 1_000);
+        ctx.spend(coin, Spend::new(puzzle, NodePtr::NIL))?;
+        let coin_spend = ctx.take().remove(0);
+
+        assert!(
+            PartialOffer::from_parent_spend(ctx, &coin_spend, &[PartialOfferAssetInfo::xch()])?
+                .is_none()
+        );
+
+        Ok(())
+    }
+}
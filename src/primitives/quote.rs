@@ -0,0 +1,201 @@
+use std::{cmp::Ordering, fmt};
+
+use chia_wallet_sdk::driver::DriverError;
+use slot_machine::CliError;
+
+use crate::{PartialPriceData, PartialReferral};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteRounding {
+    // output was rounded down (same as the puzzle's integer division)
+    Down,
+    // input was rounded up so the requested output is always reached
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    // amount of the requested asset the taker has to give
+    pub input: u64,
+    // amount of the offered asset the taker receives
+    pub output: u64,
+    // part of the taker's input that would not buy any extra output
+    pub unused: u64,
+    // paid by the taker to the referrer on top of `input`
+    pub referral_fee: u64,
+    // output / (input + referral fee)
+    pub effective_price: f64,
+    pub rounding: QuoteRounding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteError {
+    InvalidPrice,
+    Overflow,
+    BelowMinimum { minimum: u64 },
+    ExceedsAvailable { available: u64 },
+}
+
+impl fmt::Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuoteError::InvalidPrice => write!(f, "Price data contains a zero precision"),
+            QuoteError::Overflow => write!(f, "Quoted amount does not fit in a u64"),
+            QuoteError::BelowMinimum { minimum } => write!(
+                f,
+                "Amount is below the minimum accepted by the partial offer ({minimum})"
+            ),
+            QuoteError::ExceedsAvailable { available } => write!(
+                f,
+                "Partial offer can release at most {available} mojos in a single fill"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuoteError {}
+
+impl From<QuoteError> for DriverError {
+    fn from(err: QuoteError) -> Self {
+        DriverError::Custom(err.to_string())
+    }
+}
+
+impl From<QuoteError> for CliError {
+    fn from(err: QuoteError) -> Self {
+        CliError::Custom(err.to_string())
+    }
+}
+
+impl Quote {
+    pub(crate) fn new(input: u64, output: u64, unused: u64, rounding: QuoteRounding) -> Self {
+        Self {
+            input,
+            output,
+            unused,
+            referral_fee: 0,
+            effective_price: if input == 0 {
+                0.0
+            } else {
+                output as f64 / input as f64
+            },
+            rounding,
+        }
+    }
+
+    // Adds the referral fee for this quote's input (all-in price)
+    pub fn with_referral(self, referral: Option<&PartialReferral>) -> Self {
+        let referral_fee = referral.map_or(0, |referral| referral.fee(self.input));
+        let total_input = self.input + referral_fee;
+
+        Self {
+            referral_fee,
+            effective_price: if total_input == 0 {
+                0.0
+            } else {
+                self.output as f64 / total_input as f64
+            },
+            ..self
+        }
+    }
+
+    // Everything the taker pays (excl. required fees)
+    pub fn total_input(&self) -> u64 {
+        self.input + self.referral_fee
+    }
+}
+
+impl PartialPriceData {
+    fn checked_ratio(amount: u64, mul: u64, div: u64, round_up: bool) -> Result<u64, QuoteError> {
+        if mul == 0 || div == 0 {
+            return Err(QuoteError::InvalidPrice);
+        }
+
+        // u64 * u64 always fits in u128
+        let product = amount as u128 * mul as u128;
+        let result = if round_up {
+            product.div_ceil(div as u128)
+        } else {
+            product / div as u128
+        };
+
+        u64::try_from(result).map_err(|_| QuoteError::Overflow)
+    }
+
+    // Offered asset amount released by the puzzle for a given requested asset amount
+    pub fn quote(&self, input: u64) -> Result<Quote, QuoteError> {
+        let output = Self::checked_ratio(input, self.price_precision, self.precision, false)?;
+        let min_input = Self::checked_ratio(output, self.precision, self.price_precision, true)?;
+
+        Ok(Quote::new(
+            min_input,
+            output,
+            input - min_input,
+            QuoteRounding::Down,
+        ))
+    }
+
+    // Smallest requested asset amount that releases at least `output` offered asset
+    pub fn reverse_quote(&self, output: u64) -> Result<Quote, QuoteError> {
+        let input = Self::checked_ratio(output, self.precision, self.price_precision, true)?;
+
+        Ok(Quote::new(input, output, 0, QuoteRounding::Up))
+    }
+
+    // Compares offered asset released per unit of requested asset (higher is better for takers)
+    pub fn cmp_rate(&self, other: &Self) -> Ordering {
+        let rate = self.price_precision as u128 * other.precision as u128;
+        let other_rate = other.price_precision as u128 * self.precision as u128;
+        rate.cmp(&other_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_rounding() -> anyhow::Result<()> {
+        let price_data = PartialPriceData {
+            price_precision: 3,
+            precision: 7,
+        };
+
+        let quote = price_data.quote(10)?;
+        assert_eq!(quote.output, 4);
+        assert_eq!(quote.input, 10);
+        assert_eq!(quote.unused, 0);
+        assert_eq!(quote.rounding, QuoteRounding::Down);
+
+        let quote = price_data.quote(11)?;
+        assert_eq!(quote.output, 4);
+        assert_eq!(quote.input, 10);
+        assert_eq!(quote.unused, 1);
+
+        let quote = price_data.reverse_quote(4)?;
+        assert_eq!(quote.input, 10);
+        assert_eq!(quote.rounding, QuoteRounding::Up);
+        assert!(price_data.quote(quote.input)?.output >= 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_overflow() -> anyhow::Result<()> {
+        // intermediate product does not fit in a u64, but the result does
+        let price_data = PartialPriceData {
+            price_precision: 1_000_000_000_000,
+            precision: 1_000_000,
+        };
+        assert_eq!(price_data.quote(100_000_000)?.output, 100_000_000_000_000);
+        assert_eq!(price_data.quote(u64::MAX), Err(QuoteError::Overflow));
+
+        let price_data = PartialPriceData {
+            price_precision: 0,
+            precision: 1,
+        };
+        assert_eq!(price_data.quote(1), Err(QuoteError::InvalidPrice));
+
+        Ok(())
+    }
+}
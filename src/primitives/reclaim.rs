@@ -0,0 +1,128 @@
+use chia_wallet_sdk::driver::{DriverError, Spend, SpendContext};
+
+use crate::{PartialOffer, ReclaimSolution};
+
+impl PartialOffer {
+    // Sends the whole (expired) offer back to the maker through the reclaim
+    //  leaf - doesn't require any signature, so anyone can do it
+    pub fn reclaim(&self, ctx: &mut SpendContext) -> Result<(), DriverError> {
+        let Some(args) = self.info.reclaim_args() else {
+            return Err(DriverError::Custom(
+                "Partial offer is not reclaimable".to_string(),
+            ));
+        };
+
+        let puzzle = ctx.curry(args)?;
+        let solution = ctx.alloc(&ReclaimSolution {
+            my_amount: self.coin.amount,
+        })?;
+        let p2_spend = self.leaf_spend(
+            ctx,
+            self.info
+                .reclaim_puzzle_hash()
+                .ok_or(DriverError::InvalidMerkleProof)?
+                .into(),
+            Spend::new(puzzle, solution),
+        )?;
+
+        self.spend_p2(ctx, p2_spend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzle_types::{Memos, cat::CatArgs};
+    use chia_wallet_sdk::{
+        driver::{Cat, CatSpend, SpendWithConditions, StandardLayer},
+        prelude::Coin,
+        test::Simulator,
+        types::Conditions,
+    };
+
+    use crate::{PartialOfferAssetInfo, PartialOfferInfo, PartialPriceData};
+
+    use super::*;
+
+    #[test]
+    fn test_reclaim_expired_cat() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let maker_bls = sim.bls(1_000);
+        let maker = StandardLayer::new(maker_bls.pk);
+
+        let (issue_conds, cats) = Cat::issue_with_coin(
+            ctx,
+            maker_bls.coin.coin_id(),
+            1_000,
+            Conditions::new().create_coin(maker_bls.puzzle_hash, 1_000, Memos::None),
+        )?;
+        maker.spend(ctx, maker_bls.coin, issue_conds)?;
+
+        let expiration = 10_000_000_000;
+        let info = PartialOfferInfo::new(
+            Some(cats[0].child_lineage_proof()),
+            PartialOfferAssetInfo::cat(cats[0].info.asset_id, None),
+            PartialOfferAssetInfo::xch(),
+            maker_bls.puzzle_hash,
+            Some(expiration),
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        );
+        assert!(
+            PartialOffer::new(cats[0].coin.coin_id(), 1_000, info.clone())?
+                .reclaim(ctx)
+                .is_err()
+        );
+
+        let info = info.with_reclaimable(true);
+        assert_eq!(info.leaves()?.len(), 3);
+        let inner_spend = maker.spend_with_conditions(
+            ctx,
+            Conditions::new().create_coin(info.inner_puzzle_hash()?.into(), 1_000, Memos::None),
+        )?;
+        let _ = Cat::spend_all(ctx, &[CatSpend::new(cats[0], inner_spend)])?;
+        let partial_offer = PartialOffer::new(cats[0].coin.coin_id(), 1_000, info)?;
+        sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+        // can't reclaim before the offer expires
+        partial_offer.reclaim(ctx)?;
+        assert!(sim.spend_coins(ctx.take(), &[]).is_err());
+
+        // after expiration, anyone can send the coin back to the maker
+        sim.pass_time(expiration);
+        partial_offer.reclaim(ctx)?;
+        sim.spend_coins(ctx.take(), &[])?;
+
+        let reclaimed = Coin::new(
+            partial_offer.coin.coin_id(),
+            CatArgs::curry_tree_hash(cats[0].info.asset_id, maker_bls.puzzle_hash.into()).into(),
+            1_000,
+        );
+        assert!(sim.coin_state(reclaimed.coin_id()).is_some());
+
+        // offers that only expire at a block height have no reclaim leaf
+        let height_info = PartialOfferInfo {
+            expiration: None,
+            ..partial_offer.info.clone()
+        }
+        .with_expiration_height(Some(1_000_000));
+        assert!(
+            height_info
+                .clone()
+                .with_reclaimable(true)
+                .reclaim_args()
+                .is_none()
+        );
+
+        let mut hint = height_info.to_hint();
+        hint.reclaimable = true;
+        assert!(PartialOfferInfo::from_hint(&hint).is_none());
+
+        Ok(())
+    }
+}
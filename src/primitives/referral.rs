@@ -0,0 +1,81 @@
+use chia::protocol::Bytes32;
+use chia_puzzle_types::offer::{NotarizedPayment, Payment};
+use chia_wallet_sdk::driver::{DriverError, SpendContext};
+
+use crate::FEE_SPLIT_BPS_DENOMINATOR;
+
+// Fee a frontend charges the taker on top of each fill
+// Unlike fee recipients, referrals aren't part of the partial puzzle - the
+//  taker's offer simply pays them alongside the makers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PartialReferral {
+    pub puzzle_hash: Bytes32,
+    // basis points of the input (requested asset given to the maker)
+    pub bps: u64,
+}
+
+impl PartialReferral {
+    pub fn new(puzzle_hash: Bytes32, bps: u64) -> Self {
+        Self { puzzle_hash, bps }
+    }
+
+    pub fn fee(&self, input: u64) -> u64 {
+        (input as u128 * self.bps as u128 / FEE_SPLIT_BPS_DENOMINATOR as u128) as u64
+    }
+
+    // Largest input that can be paid (along with its referral fee) with `total`
+    pub fn max_input(&self, total: u64) -> u64 {
+        let mut input = (total as u128 * FEE_SPLIT_BPS_DENOMINATOR as u128
+            / (FEE_SPLIT_BPS_DENOMINATOR + self.bps) as u128) as u64;
+        // fees are rounded down, so the estimate can be a bit low
+        while input < total && input + 1 + self.fee(input + 1) <= total {
+            input += 1;
+        }
+
+        input
+    }
+
+    // Payment for the fill of `input` - the nonce is the filled coin's id
+    pub fn notarized_payment(
+        &self,
+        ctx: &mut SpendContext,
+        nonce: Bytes32,
+        input: u64,
+    ) -> Result<Option<NotarizedPayment>, DriverError> {
+        let fee = self.fee(input);
+        if fee == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(NotarizedPayment {
+            nonce,
+            payments: vec![Payment::new(
+                self.puzzle_hash,
+                fee,
+                ctx.hint(self.puzzle_hash)?,
+            )],
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referral_max_input() {
+        let referral = PartialReferral::new(Bytes32::default(), 150);
+
+        assert_eq!(referral.fee(10_000), 150);
+        assert_eq!(referral.max_input(10_150), 10_000);
+        assert_eq!(referral.max_input(10_149), 9_999);
+        // fees below 1 mojo are rounded down to 0
+        assert_eq!(referral.max_input(60), 60);
+
+        for total in [0, 1, 999, 1_000_000, u32::MAX as u64] {
+            let input = referral.max_input(total);
+            assert!(input + referral.fee(input) <= total);
+            assert!(input + 1 + referral.fee(input + 1) > total);
+        }
+    }
+}
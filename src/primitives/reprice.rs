@@ -0,0 +1,109 @@
+use chia::protocol::Bytes32;
+use chia_wallet_sdk::{
+    driver::{DriverError, Spend, SpendContext},
+    types::Conditions,
+};
+
+use crate::{PartialOffer, PartialOfferInfo};
+
+impl PartialOffer {
+    // Conditions the maker's clawback spend has to output for `reprice`
+    pub fn reprice_conditions(
+        &self,
+        ctx: &mut SpendContext,
+        new_info: &PartialOfferInfo,
+    ) -> Result<Conditions, DriverError> {
+        let inner_puzzle_hash: Bytes32 = new_info.inner_puzzle_hash()?.into();
+
+        Ok(Conditions::new().create_coin(
+            inner_puzzle_hash,
+            self.coin.amount,
+            ctx.hint(inner_puzzle_hash)?,
+        ))
+    }
+
+    // Claws back the coin and recreates it with `new_info` (e.g., a new price,
+    //  minimum amount or expiration) in the same spend, so the offer never
+    //  leaves the market
+    // `inner_spend` is the maker's spend and has to output `reprice_conditions`
+    pub fn reprice(
+        &self,
+        ctx: &mut SpendContext,
+        inner_spend: Spend,
+        new_info: PartialOfferInfo,
+    ) -> Result<Self, DriverError> {
+        if new_info.offered_asset_info != self.info.offered_asset_info {
+            return Err(DriverError::IncompatibleAssetInfo);
+        }
+
+        self.claw_back(ctx, inner_spend)?;
+
+        let lineage_proof = if self.info.offered_asset_info.asset_id.is_some() {
+            Some(self.child_lineage_proof()?)
+        } else {
+            None
+        };
+        Self::new(
+            self.coin.coin_id(),
+            self.coin.amount,
+            new_info.with_lineage_proof(lineage_proof),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia::protocol::Bytes32;
+    use chia_wallet_sdk::{
+        driver::{SpendWithConditions, StandardLayer},
+        test::Simulator,
+    };
+
+    use crate::{PartialOfferAssetInfo, PartialPriceData};
+
+    use super::*;
+
+    #[test]
+    fn test_reprice() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let maker_bls = sim.bls(0);
+        let info = PartialOfferInfo::new(
+            None,
+            PartialOfferAssetInfo::xch(),
+            PartialOfferAssetInfo::cat(Bytes32::default(), None),
+            maker_bls.puzzle_hash,
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1_000,
+                precision: 2_000,
+            },
+            0,
+        );
+        let coin = sim.new_coin(info.puzzle_hash()?, 1_000);
+        let partial_offer = PartialOffer::new(coin.parent_coin_info, 1_000, info.clone())?;
+
+        let new_info = PartialOfferInfo {
+            price_data: PartialPriceData {
+                price_precision: 1_000,
+                precision: 1_500,
+            },
+            expiration: Some(1_000_000),
+            ..info
+        };
+        let conditions = partial_offer.reprice_conditions(ctx, &new_info)?;
+        let inner_spend =
+            StandardLayer::new(maker_bls.pk).spend_with_conditions(ctx, conditions)?;
+        let repriced = partial_offer.reprice(ctx, inner_spend, new_info)?;
+        sim.spend_coins(ctx.take(), &[maker_bls.sk])?;
+
+        assert_ne!(repriced.coin.puzzle_hash, partial_offer.coin.puzzle_hash);
+        assert_eq!(repriced.coin.amount, 1_000);
+        assert_eq!(repriced.info.price_data.precision, 1_500);
+        assert!(sim.coin_state(repriced.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+}
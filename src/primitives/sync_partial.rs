@@ -0,0 +1,360 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chia::{
+    bls::Signature,
+    protocol::{Coin, SpendBundle},
+};
+use chia_puzzles::SETTLEMENT_PAYMENT_HASH;
+use chia_wallet_sdk::{coinset::ChiaRpcClient, driver::DriverError};
+
+use crate::{PartialOffer, PartialOfferInfo};
+
+#[derive(Debug, Clone)]
+pub enum PartialOfferState {
+    // latest coin is unspent (or not yet created by the offer's spend bundle)
+    Live(PartialOffer),
+    // latest coin is unspent, but can no longer be filled
+    Expired(PartialOffer),
+    // last coin was spent without being recreated (offer holds the spent coin)
+    Filled(PartialOffer),
+    ClawedBack(PartialOffer),
+}
+
+impl PartialOfferState {
+    pub fn offer(&self) -> &PartialOffer {
+        match self {
+            Self::Live(offer)
+            | Self::Expired(offer)
+            | Self::Filled(offer)
+            | Self::ClawedBack(offer) => offer,
+        }
+    }
+
+    pub fn into_offer(self) -> PartialOffer {
+        match self {
+            Self::Live(offer)
+            | Self::Expired(offer)
+            | Self::Filled(offer)
+            | Self::ClawedBack(offer) => offer,
+        }
+    }
+
+    pub fn is_live(&self) -> bool {
+        matches!(self, Self::Live(_))
+    }
+}
+
+impl fmt::Display for PartialOfferState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Live(_) => write!(f, "live"),
+            Self::Expired(_) => write!(f, "expired"),
+            Self::Filled(_) => write!(f, "fully filled"),
+            Self::ClawedBack(_) => write!(f, "clawed back"),
+        }
+    }
+}
+
+pub fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn rpc_error(err: impl fmt::Display) -> DriverError {
+    DriverError::Custom(format!("RPC request failed: {err}"))
+}
+
+// Height of the next block - the earliest one that can include a spend
+pub async fn next_block_height<C>(client: &C) -> Result<u32, DriverError>
+where
+    C: ChiaRpcClient,
+    C::Error: fmt::Display,
+{
+    let Some(blockchain_state) = client
+        .get_blockchain_state()
+        .await
+        .map_err(rpc_error)?
+        .blockchain_state
+    else {
+        return Err(DriverError::Custom(
+            "Could not get the blockchain state".to_string(),
+        ));
+    };
+
+    Ok(blockchain_state.peak.height + 1)
+}
+
+impl PartialOffer {
+    pub fn is_expired(&self, now: u64) -> bool {
+        // ASSERT_BEFORE_SECONDS_ABSOLUTE fails once the timestamp is reached
+        self.info
+            .expiration
+            .is_some_and(|expiration| now >= expiration)
+    }
+
+    // Checks both the (local) timestamp and the next block's height - the peak
+    //  is only looked up for offers that expire at a height
+    pub async fn is_expired_now<C>(&self, client: &C) -> Result<bool, DriverError>
+    where
+        C: ChiaRpcClient,
+        C::Error: fmt::Display,
+    {
+        Ok(self.is_expired(current_timestamp())
+            || (self.info.expiration_height.is_some()
+                && self
+                    .info
+                    .is_expired_at_height(next_block_height(client).await?)))
+    }
+
+    // State of the offer while its coin is unspent
+    pub fn unspent_state(self, expired: bool) -> PartialOfferState {
+        if expired {
+            PartialOfferState::Expired(self)
+        } else {
+            PartialOfferState::Live(self)
+        }
+    }
+
+    // State of the offer once its coin was spent without being recreated -
+    //  fills create a settlement payment coin, clawbacks don't
+    pub fn spent_state(self, children: &[Coin]) -> PartialOfferState {
+        let settlement_puzzle_hash = PartialOfferInfo::full_asset_puzzle_hash(
+            self.info.offered_asset_info,
+            SETTLEMENT_PAYMENT_HASH.into(),
+        );
+
+        if children
+            .iter()
+            .any(|child| child.puzzle_hash == settlement_puzzle_hash)
+        {
+            PartialOfferState::Filled(self)
+        } else {
+            PartialOfferState::ClawedBack(self)
+        }
+    }
+
+    // Follows this (spent) coin's lineage through `records` - (coin, spent)
+    //  pairs, e.g., from a hint lookup - and returns the latest known coin, the
+    //  coins found after this one and whether the latest coin is unspent
+    pub fn follow_lineage(
+        self,
+        records: &[(Coin, bool)],
+    ) -> Result<(Self, Vec<Coin>, bool), DriverError> {
+        // recreated coins keep the puzzle hash, so each coin has at most one child here
+        let children = records
+            .iter()
+            .filter(|(coin, _)| coin.puzzle_hash == self.coin.puzzle_hash)
+            .map(|(coin, spent)| (coin.parent_coin_info, (coin.amount, *spent)))
+            .collect::<HashMap<_, _>>();
+
+        let mut current = self;
+        let mut coins = Vec::new();
+        while let Some(&(amount, spent)) = children.get(&current.coin.coin_id()) {
+            current = current.child(amount)?;
+            coins.push(current.coin);
+
+            if !spent {
+                return Ok((current, coins, true));
+            }
+        }
+
+        Ok((current, coins, false))
+    }
+
+    // Follows the offer's lineage to its latest coin
+    pub async fn sync<C>(self, client: &C) -> Result<PartialOfferState, DriverError>
+    where
+        C: ChiaRpcClient,
+        C::Error: fmt::Display,
+    {
+        Ok(self.sync_lineage(client).await?.0)
+    }
+
+    // Same as sync, but also returns every coin in the lineage (starting with this one)
+    // Recreated coins are hinted with the partial inner puzzle hash, so one
+    //  hint lookup returns the whole lineage
+    pub async fn sync_lineage<C>(
+        mut self,
+        client: &C,
+    ) -> Result<(PartialOfferState, Vec<Coin>), DriverError>
+    where
+        C: ChiaRpcClient,
+        C::Error: fmt::Display,
+    {
+        let mut coins = vec![self.coin];
+
+        let Some(record) = client
+            .get_coin_record_by_name(self.coin.coin_id())
+            .await
+            .map_err(rpc_error)?
+            .coin_record
+        else {
+            // offer's spend bundle was not pushed yet
+            let expired = self.is_expired_now(client).await?;
+            return Ok((self.unspent_state(expired), coins));
+        };
+
+        // the coin already exists, so the spends that create it (which may also
+        //  create other offers, e.g., ladder rungs) must not be pushed again
+        self.spend_bundle = SpendBundle::new(Vec::new(), Signature::default());
+
+        if !record.spent {
+            let expired = self.is_expired_now(client).await?;
+            return Ok((self.unspent_state(expired), coins));
+        }
+
+        let hint = self.info.inner_puzzle_hash()?.into();
+        let mut min_height = record.confirmed_block_index;
+        let mut current = self;
+        loop {
+            let lineage = client
+                .get_coin_records_by_hint(hint, Some(min_height), None, Some(true))
+                .await
+                .map_err(rpc_error)?
+                .coin_records
+                .unwrap_or_default()
+                .into_iter()
+                .map(|cr| (cr.coin, cr.spent))
+                .collect::<Vec<_>>();
+
+            let (latest, new_coins, unspent) = current.follow_lineage(&lineage)?;
+            current = latest;
+            coins.extend(new_coins);
+            if unspent {
+                let expired = current.is_expired_now(client).await?;
+                return Ok((current.unspent_state(expired), coins));
+            }
+
+            // the hint lookup may have missed the next coin, so check the
+            //  latest coin's children before calling the offer filled
+            let children = client
+                .get_coin_records_by_parent_ids(
+                    vec![current.coin.coin_id()],
+                    None,
+                    None,
+                    Some(true),
+                )
+                .await
+                .map_err(rpc_error)?
+                .coin_records
+                .unwrap_or_default();
+            let Some(child_record) = children
+                .iter()
+                .find(|cr| cr.coin.puzzle_hash == current.coin.puzzle_hash)
+            else {
+                // spent & not recreated - either the last fill or a clawback
+                let children = children.into_iter().map(|cr| cr.coin).collect::<Vec<_>>();
+                return Ok((current.spent_state(&children), coins));
+            };
+
+            current = current.child(child_record.coin.amount)?;
+            coins.push(current.coin);
+            if !child_record.spent {
+                let expired = current.is_expired_now(client).await?;
+                return Ok((current.unspent_state(expired), coins));
+            }
+            min_height = child_record.confirmed_block_index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia::protocol::Bytes32;
+
+    use crate::{PartialOfferAssetInfo, primitives::partial::tests::offer_with_price};
+
+    use super::*;
+
+    #[test]
+    fn test_partial_offer_states() -> anyhow::Result<()> {
+        let partial_offer = offer_with_price(Bytes32::new([1; 32]), 1_000, 100, Some(500))?;
+        assert!(!partial_offer.is_expired(499));
+        assert!(partial_offer.is_expired(500));
+
+        let state = partial_offer
+            .clone()
+            .unspent_state(partial_offer.is_expired(499));
+        assert!(matches!(state, PartialOfferState::Live(_)));
+        assert!(state.is_live());
+        let state = partial_offer
+            .clone()
+            .unspent_state(partial_offer.is_expired(500));
+        assert!(matches!(state, PartialOfferState::Expired(_)));
+        assert!(!state.is_live());
+
+        // fills pay the taker through a settlement coin of the offered asset
+        let settlement_coin = Coin::new(
+            partial_offer.coin.coin_id(),
+            SETTLEMENT_PAYMENT_HASH.into(),
+            1_000,
+        );
+        let state = partial_offer.clone().spent_state(&[settlement_coin]);
+        assert!(matches!(state, PartialOfferState::Filled(_)));
+        assert_eq!(state.offer().coin, partial_offer.coin);
+
+        // clawbacks (and CAT settlement coins for XCH offers) don't count
+        let maker_coin = Coin::new(partial_offer.coin.coin_id(), Bytes32::default(), 1_000);
+        let cat_settlement_coin = Coin::new(
+            partial_offer.coin.coin_id(),
+            PartialOfferInfo::full_asset_puzzle_hash(
+                PartialOfferAssetInfo::cat(Bytes32::default(), None),
+                SETTLEMENT_PAYMENT_HASH.into(),
+            ),
+            1_000,
+        );
+        for children in [vec![], vec![maker_coin], vec![cat_settlement_coin]] {
+            let state = partial_offer.clone().spent_state(&children);
+            assert!(matches!(state, PartialOfferState::ClawedBack(_)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_follow_lineage() -> anyhow::Result<()> {
+        let partial_offer = offer_with_price(Bytes32::new([1; 32]), 1_000, 100, None)?;
+        let first_child = partial_offer.child(600)?.coin;
+        let second_child = partial_offer.child(600)?.child(300)?.coin;
+        // fills also create coins with other puzzle hashes
+        let settlement_coin = Coin::new(
+            partial_offer.coin.coin_id(),
+            SETTLEMENT_PAYMENT_HASH.into(),
+            400,
+        );
+
+        // records can come in any order
+        let (latest, coins, unspent) = partial_offer.clone().follow_lineage(&[
+            (second_child, false),
+            (settlement_coin, false),
+            (first_child, true),
+        ])?;
+        assert_eq!(latest.coin, second_child);
+        assert_eq!(coins, vec![first_child, second_child]);
+        assert!(unspent);
+
+        // lineage ends at a spent coin
+        let (latest, coins, unspent) = partial_offer
+            .clone()
+            .follow_lineage(&[(first_child, true), (second_child, true)])?;
+        assert_eq!(latest.coin, second_child);
+        assert_eq!(coins, vec![first_child, second_child]);
+        assert!(!unspent);
+
+        // missing coins stop the walk
+        let (latest, coins, unspent) = partial_offer
+            .clone()
+            .follow_lineage(&[(second_child, false)])?;
+        assert_eq!(latest.coin, partial_offer.coin);
+        assert!(coins.is_empty());
+        assert!(!unspent);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use chia::protocol::Bytes32;
+use chia_wallet_sdk::{
+    driver::{Cat, CatInfo, CatSpend, DriverError, Spend, SpendContext},
+    types::Conditions,
+};
+
+use crate::{PartialCurve, PartialOffer};
+
+impl PartialOffer {
+    // Conditions the maker's clawback spend has to output for `top_up`
+    // The new coin is hinted like the ones recreated by fills, so it's part of
+    //  the offer's lineage
+    pub fn top_up_conditions(
+        &self,
+        ctx: &mut SpendContext,
+        extra_amount: u64,
+    ) -> Result<Conditions, DriverError> {
+        let inner_puzzle_hash: Bytes32 = self.info.inner_puzzle_hash()?.into();
+
+        Ok(Conditions::new().create_coin(
+            inner_puzzle_hash,
+            self.coin.amount + extra_amount,
+            ctx.hint(inner_puzzle_hash)?,
+        ))
+    }
+
+    // Adds `extra_amount` of the offered asset to a live offer without changing
+    //  its info (and thus its puzzle hash)
+    // The coin is spent through the clawback leaf - `inner_spend` has to output
+    //  `top_up_conditions` and the extra amount has to come from other coins
+    //  in the same spend bundle (for CAT offers, `extra_cat_spends` are spent in
+    //  the same ring)
+    pub fn top_up(
+        &self,
+        ctx: &mut SpendContext,
+        inner_spend: Spend,
+        extra_amount: u64,
+        extra_cat_spends: Vec<CatSpend>,
+    ) -> Result<Self, DriverError> {
+        // linear curves are only defined up to their total amount
+        if let Some(PartialCurve::Linear(curve)) = self.info.curve
+            && self.coin.amount + extra_amount > curve.total_amount
+        {
+            return Err(DriverError::Custom(
+                "Linear curve offers can't hold more than their total amount".to_string(),
+            ));
+        }
+
+        if let Some(asset_id) = self.info.offered_asset_info.asset_id {
+            let cat = Cat::new(
+                self.coin,
+                self.info.lineage_proof,
+                CatInfo::new(
+                    asset_id,
+                    self.info.offered_asset_info.hidden_puzzle_hash,
+                    self.info.inner_puzzle_hash()?.into(),
+                ),
+            );
+            let leaf_spend = self.clawback_leaf_spend(ctx, inner_spend)?;
+
+            let _ = Cat::spend_all(
+                ctx,
+                &[vec![CatSpend::new(cat, leaf_spend)], extra_cat_spends].concat(),
+            )?;
+        } else {
+            if !extra_cat_spends.is_empty() {
+                return Err(DriverError::IncompatibleAssetInfo);
+            }
+
+            self.claw_back(ctx, inner_spend)?;
+        }
+
+        self.child(self.coin.amount + extra_amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia::protocol::Coin;
+    use chia_puzzle_types::Memos;
+    use chia_wallet_sdk::{
+        driver::{SpendWithConditions, StandardLayer},
+        test::Simulator,
+    };
+
+    use crate::{PartialOfferAssetInfo, PartialOfferInfo, PartialPriceData};
+
+    use super::*;
+
+    #[test]
+    fn test_top_up() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        for offered_is_cat in [false, true] {
+            let maker_bls = sim.bls(1_500);
+            let maker = StandardLayer::new(maker_bls.pk);
+
+            let info = PartialOfferInfo::new(
+                None,
+                PartialOfferAssetInfo::xch(),
+                PartialOfferAssetInfo::cat(Bytes32::default(), None),
+                maker_bls.puzzle_hash,
+                None,
+                None,
+                PartialPriceData {
+                    price_precision: 1,
+                    precision: 1,
+                },
+                0,
+            );
+
+            // create a 1,000 mojo offer and keep 500 mojos for the top-up
+            let (partial_offer, change_cat) = if offered_is_cat {
+                let (issue_conds, cats) = Cat::issue_with_coin(
+                    ctx,
+                    maker_bls.coin.coin_id(),
+                    1_500,
+                    Conditions::new().create_coin(maker_bls.puzzle_hash, 1_500, Memos::None),
+                )?;
+                maker.spend(ctx, maker_bls.coin, issue_conds)?;
+
+                let info = PartialOfferInfo {
+                    offered_asset_info: PartialOfferAssetInfo::cat(cats[0].info.asset_id, None),
+                    requested_asset_info: PartialOfferAssetInfo::xch(),
+                    ..info
+                }
+                .with_lineage_proof(Some(cats[0].child_lineage_proof()));
+                let inner_puzzle_hash: Bytes32 = info.inner_puzzle_hash()?.into();
+
+                let inner_spend = maker.spend_with_conditions(
+                    ctx,
+                    Conditions::new()
+                        .create_coin(inner_puzzle_hash, 1_000, Memos::None)
+                        .create_coin(maker_bls.puzzle_hash, 500, Memos::None),
+                )?;
+                let _ = Cat::spend_all(ctx, &[CatSpend::new(cats[0], inner_spend)])?;
+
+                (
+                    PartialOffer::new(cats[0].coin.coin_id(), 1_000, info)?,
+                    Some(cats[0].child(maker_bls.puzzle_hash, 500)),
+                )
+            } else {
+                maker.spend(
+                    ctx,
+                    maker_bls.coin,
+                    Conditions::new()
+                        .create_coin(info.puzzle_hash()?, 1_000, Memos::None)
+                        .create_coin(maker_bls.puzzle_hash, 500, Memos::None),
+                )?;
+
+                (
+                    PartialOffer::new(maker_bls.coin.coin_id(), 1_000, info)?,
+                    None,
+                )
+            };
+            sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+            let top_up_conditions = partial_offer.top_up_conditions(ctx, 500)?;
+            let inner_spend = maker.spend_with_conditions(ctx, top_up_conditions)?;
+            let extra_cat_spends = if let Some(change_cat) = change_cat {
+                let change_spend = maker.spend_with_conditions(ctx, Conditions::new())?;
+                vec![CatSpend::new(change_cat, change_spend)]
+            } else {
+                maker.spend(
+                    ctx,
+                    Coin::new(maker_bls.coin.coin_id(), maker_bls.puzzle_hash, 500),
+                    Conditions::new(),
+                )?;
+                vec![]
+            };
+
+            let topped_up = partial_offer.top_up(ctx, inner_spend, 500, extra_cat_spends)?;
+            sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+            assert_eq!(topped_up.coin.amount, 1_500);
+            assert_eq!(topped_up.coin.puzzle_hash, partial_offer.coin.puzzle_hash);
+            assert!(sim.coin_state(topped_up.coin.coin_id()).is_some());
+            assert_eq!(topped_up.info.lineage_proof.is_some(), offered_is_cat);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use chia::protocol::Bytes32;
+use chia_wallet_sdk::{
+    driver::{DriverError, Spend, SpendContext},
+    types::Conditions,
+};
+
+use crate::PartialOffer;
+
+impl PartialOffer {
+    // Conditions the maker's clawback spend has to output for `withdraw`
+    // Whatever is left is recreated (and hinted) under the same puzzle hash
+    pub fn withdraw_conditions(
+        &self,
+        ctx: &mut SpendContext,
+        amount: u64,
+    ) -> Result<Conditions, DriverError> {
+        if amount == 0 || amount > self.coin.amount {
+            return Err(DriverError::Custom(format!(
+                "Can only withdraw between 1 and {} mojos",
+                self.coin.amount
+            )));
+        }
+
+        let mut conditions = Conditions::new().create_coin(
+            self.info.clawback_puzzle_hash,
+            amount,
+            ctx.hint(self.info.clawback_puzzle_hash)?,
+        );
+        if amount < self.coin.amount {
+            let inner_puzzle_hash: Bytes32 = self.info.inner_puzzle_hash()?.into();
+            conditions = conditions.create_coin(
+                inner_puzzle_hash,
+                self.coin.amount - amount,
+                ctx.hint(inner_puzzle_hash)?,
+            );
+        }
+
+        Ok(conditions)
+    }
+
+    // Sends `amount` back to the maker and leaves the rest of the offer open
+    // `inner_spend` is the maker's spend and has to output `withdraw_conditions`
+    // Returns the remaining offer, if anything is left
+    pub fn withdraw(
+        &self,
+        ctx: &mut SpendContext,
+        inner_spend: Spend,
+        amount: u64,
+    ) -> Result<Option<Self>, DriverError> {
+        self.claw_back(ctx, inner_spend)?;
+
+        Ok(if amount < self.coin.amount {
+            Some(self.child(self.coin.amount - amount)?)
+        } else {
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzle_types::Memos;
+    use chia_wallet_sdk::{
+        driver::{Cat, CatSpend, SpendWithConditions, StandardLayer},
+        test::Simulator,
+    };
+
+    use crate::{PartialOfferAssetInfo, PartialOfferInfo, PartialPriceData};
+
+    use super::*;
+
+    #[test]
+    fn test_withdraw_cat() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+        let mut sim = Simulator::new();
+
+        let maker_bls = sim.bls(1_000);
+        let maker = StandardLayer::new(maker_bls.pk);
+
+        let (issue_conds, cats) = Cat::issue_with_coin(
+            ctx,
+            maker_bls.coin.coin_id(),
+            1_000,
+            Conditions::new().create_coin(maker_bls.puzzle_hash, 1_000, Memos::None),
+        )?;
+        maker.spend(ctx, maker_bls.coin, issue_conds)?;
+
+        let info = PartialOfferInfo::new(
+            Some(cats[0].child_lineage_proof()),
+            PartialOfferAssetInfo::cat(cats[0].info.asset_id, None),
+            PartialOfferAssetInfo::xch(),
+            maker_bls.puzzle_hash,
+            None,
+            None,
+            PartialPriceData {
+                price_precision: 1,
+                precision: 1,
+            },
+            0,
+        );
+        let inner_spend = maker.spend_with_conditions(
+            ctx,
+            Conditions::new().create_coin(info.inner_puzzle_hash()?.into(), 1_000, Memos::None),
+        )?;
+        let _ = Cat::spend_all(ctx, &[CatSpend::new(cats[0], inner_spend)])?;
+        let partial_offer = PartialOffer::new(cats[0].coin.coin_id(), 1_000, info)?;
+        sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+        // withdraw part of the offer
+        let conditions = partial_offer.withdraw_conditions(ctx, 400)?;
+        let inner_spend = maker.spend_with_conditions(ctx, conditions)?;
+        let remaining = partial_offer.withdraw(ctx, inner_spend, 400)?.unwrap();
+        sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+        assert_eq!(remaining.coin.amount, 600);
+        assert_eq!(remaining.coin.puzzle_hash, partial_offer.coin.puzzle_hash);
+        assert!(sim.coin_state(remaining.coin.coin_id()).is_some());
+
+        // remaining coin is spendable (i.e., its lineage proof is correct)
+        let conditions = remaining.withdraw_conditions(ctx, 600)?;
+        let inner_spend = maker.spend_with_conditions(ctx, conditions)?;
+        assert!(remaining.withdraw(ctx, inner_spend, 600)?.is_none());
+        sim.spend_coins(ctx.take(), &[maker_bls.sk.clone()])?;
+
+        assert!(partial_offer.withdraw_conditions(ctx, 1_001).is_err());
+
+        Ok(())
+    }
+}
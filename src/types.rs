@@ -1,8 +1,20 @@
+mod fill_authorizer;
 mod partial;
+mod partial_curve;
+mod partial_fee_split;
 mod partial_hint;
+mod reclaim;
+mod taker_allowlist;
+mod timelock;
 
+pub use fill_authorizer::*;
 pub use partial::*;
+pub use partial_curve::*;
+pub use partial_fee_split::*;
 pub use partial_hint::*;
+pub use reclaim::*;
+pub use taker_allowlist::*;
+pub use timelock::*;
 
 #[cfg(test)]
 mod tests {
@@ -22,6 +34,14 @@ mod tests {
     #[test]
     fn test_puzzle_hashes() -> anyhow::Result<()> {
         assert_puzzle_hash!(PARTIAL_PUZZLE => PARTIAL_PUZZLE_HASH);
+        assert_puzzle_hash!(PARTIAL_CURVE_PUZZLE => PARTIAL_CURVE_PUZZLE_HASH);
+        assert_puzzle_hash!(LINEAR_CURVE_PUZZLE => LINEAR_CURVE_PUZZLE_HASH);
+        assert_puzzle_hash!(CONSTANT_PRODUCT_CURVE_PUZZLE => CONSTANT_PRODUCT_CURVE_PUZZLE_HASH);
+        assert_puzzle_hash!(PARTIAL_FEE_SPLIT_PUZZLE => PARTIAL_FEE_SPLIT_PUZZLE_HASH);
+        assert_puzzle_hash!(RECLAIM_PUZZLE => RECLAIM_PUZZLE_HASH);
+        assert_puzzle_hash!(TIMELOCK_PUZZLE => TIMELOCK_PUZZLE_HASH);
+        assert_puzzle_hash!(TAKER_ALLOWLIST_PUZZLE => TAKER_ALLOWLIST_PUZZLE_HASH);
+        assert_puzzle_hash!(FILL_AUTHORIZER_PUZZLE => FILL_AUTHORIZER_PUZZLE_HASH);
 
         Ok(())
     }
@@ -0,0 +1,52 @@
+use std::borrow::Cow;
+
+use chia::{bls::PublicKey, clvm_utils::TreeHash};
+use chia_wallet_sdk::types::Mod;
+use clvm_traits::{FromClvm, ToClvm};
+use hex_literal::hex;
+
+pub const FILL_AUTHORIZER_PUZZLE: [u8; 241] = hex!(
+    "
+    ff02ffff01ff04ffff04ffff0132ffff04ff05ffff04ffff02ff04ffff04ff04
+    ffff04ff17ff80808080ff80808080ffff02ff0affff04ff02ffff04ffff02ff
+    17ff2f80ffff04ffff02ff0bff3f80ff808080808080ffff04ffff01ffff02ff
+    ff03ffff07ff0580ffff01ff0bffff0102ffff02ff02ffff04ff02ffff04ffff
+    05ff0580ff80808080ffff02ff02ffff04ff02ffff04ffff06ff0580ff808080
+    8080ffff01ff0bffff0101ff058080ff0180ffff02ffff03ff05ffff01ff04ff
+    ff05ff0580ffff02ff0affff04ff02ffff04ffff06ff0580ffff04ff0bff8080
+    80808080ffff010b80ff018080ff018080
+    "
+);
+
+pub const FILL_AUTHORIZER_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    06af8f4faa96136a6cdabc7eba06723caf46f471826234274b68f0170c3d7813
+    "
+));
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct FillAuthorizerArgs<I> {
+    pub authorizer_key: PublicKey,
+    pub inner_puzzle: I,
+}
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct FillAuthorizerSolution<P, S, I> {
+    // signed by the authorizer (AGG_SIG_ME of its tree hash)
+    pub delegated_puzzle: P,
+    pub delegated_solution: S,
+    #[clvm(rest)]
+    pub inner_solution: I,
+}
+
+impl<I> Mod for FillAuthorizerArgs<I> {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&FILL_AUTHORIZER_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        FILL_AUTHORIZER_PUZZLE_HASH
+    }
+}
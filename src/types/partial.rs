@@ -39,6 +39,23 @@ pub struct PartialPriceData {
     pub precision: u64,
 }
 
+// Price moves linearly from the offer's price data to `end_precision` (same
+//  `price_precision`) between `start_time` and `end_time`, in `steps` steps
+// Each step is a separate fixed-price partial puzzle leaf asserting its own
+//  time window, so the price follows a staircase instead of decaying
+//  continuously - there's no puzzle that computes the price from the time yet
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct PartialDutchAuction {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub end_precision: u64,
+    pub steps: u64,
+}
+
+// Every step adds a leaf to the offer's merkle tree
+pub const MAX_DUTCH_STEPS: u64 = 64;
+
 #[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
 #[clvm(curry)]
 pub struct PartialPuzzleArgs<CM, IP> {
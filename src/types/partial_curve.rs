@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+
+use chia::{clvm_utils::TreeHash, protocol::Bytes32};
+use chia_puzzle_types::CoinProof;
+use chia_wallet_sdk::types::Mod;
+use clvm_traits::{FromClvm, ToClvm};
+use hex_literal::hex;
+
+pub const PARTIAL_CURVE_PUZZLE: [u8; 528] = hex!(
+    "
+    ff02ffff01ff04ffff04ffff013fffff04ffff0bff0bffff02ff02ffff04ff02
+    ffff04ffff04ff82027fffff04ffff04ff17ffff04ffff02ffff03ffff15ff82
+    02ffff5f80ffff018202ffffff01ff088080ff0180ffff04ffff04ff17ff8080
+    ff80808080ff808080ff8080808080ff808080ffff04ffff04ffff0146ffff04
+    ffff30ff82027fffff02ff05ffff04ff82057fff8217ff8080ff820b7f80ff80
+    8080ffff04ffff02ffff03ffff02ffff03ffff15ff820b7fff8205ff80ffff01
+    ff02ffff03ffff15ff8205ffffff0181ff80ffff01ff02ff81bfffff04ff820b
+    7fffff04ff8205ffffff04ff8202ffff8080808080ffff018080ff0180ffff01
+    8080ff0180ffff01ff02ffff03ffff15ff8205ffff8080ffff01ff04ffff0133
+    ffff04ff82057fffff04ff8205ffffff04ffff04ff82057fff8080ff80808080
+    80ffff01ff04ffff0101ff808080ff0180ffff01ff088080ff0180ffff02ffff
+    03ffff02ffff03ff820bffffff01ff15ff822bffffff0181ff80ffff018080ff
+    0180ffff01ff04ffff04ffff0133ff820bff80ffff02ff2fff821fff8080ffff
+    01ff02ff2fff821fff8080ff0180808080ffff04ffff01ff02ffff03ffff07ff
+    0580ffff01ff0bffff0102ffff02ff02ffff04ff02ffff04ffff05ff0580ff80
+    808080ffff02ff02ffff04ff02ffff04ffff06ff0580ff8080808080ffff01ff
+    0bffff0101ff058080ff0180ff018080
+    "
+);
+
+pub const PARTIAL_CURVE_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    31acd287259aefb95f497b0110ec32e23029e9779a27a15ae0c6d86b232f1b8d
+    "
+));
+
+pub const LINEAR_CURVE_PUZZLE: [u8; 84] = hex!(
+    "
+    ff15ffff10ffff12ffff0102ff02ff05ff81bf80ffff010180ffff12ffff11ff
+    2fff5f80ffff10ffff12ffff0102ff02ff0b80ffff12ffff11ff17ff0b80ffff
+    11ffff12ffff0102ff0280ff2fff5f8080808080
+    "
+);
+
+pub const LINEAR_CURVE_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    4ec7534fe3e207a29ddf72c6f4730850078216c968e8023ee834ecb2c040b8d2
+    "
+));
+
+pub const CONSTANT_PRODUCT_CURVE_PUZZLE: [u8; 35] = hex!(
+    "
+    ff15ffff10ffff12ff17ff05ff0b80ffff010180ffff12ff02ffff11ff05ff0b
+    808080
+    "
+);
+
+pub const CONSTANT_PRODUCT_CURVE_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    970d42f1dae3eed2029998a05907b50ffac34f6d48f67b4b3c84fc50690cdd13
+    "
+));
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct PartialCurvePuzzleArgs<CM, IP, C> {
+    pub cat_maker: CM,
+    pub other_asset_offer_mod: Bytes32,
+    pub receiver_puzzle_hash: Bytes32,
+    pub inner_puzzle: IP,
+    pub min_other_asset_amount_minus_one: u64,
+    pub curve: C,
+}
+
+#[derive(FromClvm, ToClvm, Debug, Clone, PartialEq, Eq)]
+#[clvm(list)]
+pub struct PartialCurveSolution<CCR, CMS, IPS> {
+    pub my_data: CoinProof,
+    pub other_asset_amount: u64,
+    pub new_amount: u64,
+    pub create_coin_rest: Option<CCR>,
+    pub cat_maker_solution: CMS,
+    #[clvm(rest)]
+    pub inner_puzzle_solution: IPS,
+}
+
+impl<CM, IP, C> Mod for PartialCurvePuzzleArgs<CM, IP, C> {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&PARTIAL_CURVE_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        PARTIAL_CURVE_PUZZLE_HASH
+    }
+}
+
+// Price goes from START_PRECISION (coin holds TOTAL_AMOUNT) to END_PRECISION
+//  (coin is empty), both per PRICE_PRECISION
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct LinearCurveArgs {
+    pub total_amount: u64,
+    pub price_precision: u64,
+    pub start_precision: u64,
+    pub end_precision: u64,
+}
+
+impl Mod for LinearCurveArgs {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&LINEAR_CURVE_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        LINEAR_CURVE_PUZZLE_HASH
+    }
+}
+
+// amount * other asset reserve = K
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct ConstantProductCurveArgs {
+    pub k: u128,
+}
+
+impl Mod for ConstantProductCurveArgs {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&CONSTANT_PRODUCT_CURVE_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        CONSTANT_PRODUCT_CURVE_PUZZLE_HASH
+    }
+}
+
+// Curve parameters, as stored in the partial offer hint
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct PartialLinearCurve {
+    pub total_amount: u64,
+    pub price_precision: u64,
+    pub start_precision: u64,
+    pub end_precision: u64,
+}
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct PartialConstantProductCurve {
+    pub k: u128,
+}
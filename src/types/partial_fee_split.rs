@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+
+use chia::{clvm_utils::TreeHash, protocol::Bytes32};
+use chia_wallet_sdk::types::Mod;
+use clvm_traits::{FromClvm, ToClvm};
+use hex_literal::hex;
+
+use crate::PartialPriceData;
+
+pub const PARTIAL_FEE_SPLIT_PUZZLE: [u8; 853] = hex!(
+    "
+    ff02ffff01ff04ffff02ff0affff04ff02ffff04ff0bffff04ff8204ffffff04
+    ff17ffff04ffff11ffff02ffff03ffff15ff8205ffff81bf80ffff018205ffff
+    ff01ff088080ff0180ffff02ff2effff04ff02ffff04ff2fffff04ff8205ffff
+    808080808080ff80808080808080ffff02ff16ffff04ff02ffff04ff0bffff04
+    ff8204ffffff04ff2fffff04ff8205ffffff04ffff04ffff04ffff0146ffff04
+    ffff30ff8204ffffff02ff05ffff04ff820affff8217ff8080ff8216ff80ff80
+    8080ffff04ffff02ffff03ffff15ffff11ff8216ffffff13ffff12ff8205ffff
+    82027f80ff82037f8080ff8080ffff01ff04ffff0133ffff04ff820affffff04
+    ffff11ff8216ffffff13ffff12ff8205ffff82027f80ff82037f8080ffff04ff
+    ff04ff820affff8080ff8080808080ffff01ff04ffff0101ff808080ff0180ff
+    ff02ffff03ffff02ffff03ff820bffffff01ff15ff822bffffff0181ff80ffff
+    018080ff0180ffff01ff04ffff04ffff0133ff820bff80ffff02ff5fff821fff
+    8080ffff01ff02ff5fff821fff8080ff01808080ff808080808080808080ffff
+    04ffff01ffff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff04ffff
+    04ff02ffff04ffff05ff0580ff80808080ffff02ff04ffff04ff02ffff04ffff
+    06ff0580ff8080808080ffff01ff0bffff0101ff058080ff0180ffff04ffff01
+    3fffff04ffff0bff05ffff02ff04ffff04ff02ffff04ffff04ff0bffff04ffff
+    04ff17ffff04ff2fffff04ffff04ff17ff8080ff80808080ff808080ff808080
+    8080ff808080ffff02ffff03ff17ffff01ff02ff16ffff04ff02ffff04ff05ff
+    ff04ff0bffff04ffff06ff1780ffff04ff2fffff04ffff02ffff03ffff15ffff
+    13ffff12ff2fffff06ffff05ff17808080ffff0182271080ff8080ffff01ff04
+    ffff02ff0affff04ff02ffff04ff05ffff04ff0bffff04ffff05ffff05ff1780
+    80ffff04ffff13ffff12ff2fffff06ffff05ff17808080ffff0182271080ff80
+    808080808080ff5f80ffff015f80ff0180ff8080808080808080ffff015f80ff
+    0180ffff02ffff03ff05ffff01ff10ffff13ffff12ff0bffff06ffff05ff0580
+    8080ffff0182271080ffff02ff2effff04ff02ffff04ffff06ff0580ffff04ff
+    0bff808080808080ffff018080ff018080ff018080
+    "
+);
+
+pub const PARTIAL_FEE_SPLIT_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    37be0838845544a5012e6ce6b7910386c47ff9ea1666b990e77d0bdca4b47f6c
+    "
+));
+
+// Basis points are out of 10000
+pub const FEE_SPLIT_BPS_DENOMINATOR: u64 = 10_000;
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[clvm(list)]
+pub struct PartialFeeRecipient {
+    pub puzzle_hash: Bytes32,
+    #[clvm(rest)]
+    pub bps: u64,
+}
+
+impl PartialFeeRecipient {
+    pub fn fee(&self, other_asset_amount: u64) -> u64 {
+        (other_asset_amount as u128 * self.bps as u128 / FEE_SPLIT_BPS_DENOMINATOR as u128) as u64
+    }
+
+    // The receiver always gets something and each recipient is paid once
+    pub fn are_valid(fee_recipients: &[Self]) -> bool {
+        let total_bps = fee_recipients
+            .iter()
+            .try_fold(0u64, |total, recipient| total.checked_add(recipient.bps));
+
+        total_bps.is_some_and(|total_bps| total_bps < FEE_SPLIT_BPS_DENOMINATOR)
+            && fee_recipients.iter().enumerate().all(|(i, recipient)| {
+                fee_recipients[..i]
+                    .iter()
+                    .all(|other| other.puzzle_hash != recipient.puzzle_hash)
+            })
+    }
+}
+
+// Uses the same solution as the partial puzzle (`PartialSolution`)
+#[derive(ToClvm, FromClvm, Debug, Clone, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct PartialFeeSplitPuzzleArgs<CM, IP> {
+    pub cat_maker: CM,
+    pub other_asset_offer_mod: Bytes32,
+    pub receiver_puzzle_hash: Bytes32,
+    pub fee_recipients: Vec<PartialFeeRecipient>,
+    pub inner_puzzle: IP,
+    pub min_other_asset_amount_minus_one: u64,
+    pub price_data: PartialPriceData,
+}
+
+impl<CM, IP> Mod for PartialFeeSplitPuzzleArgs<CM, IP> {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&PARTIAL_FEE_SPLIT_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        PARTIAL_FEE_SPLIT_PUZZLE_HASH
+    }
+}
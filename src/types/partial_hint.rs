@@ -1,10 +1,13 @@
-use chia::protocol::Bytes32;
+use chia::{bls::PublicKey, protocol::Bytes32};
 use chia_puzzle_types::LineageProof;
 use clvm_traits::{FromClvm, ToClvm};
 
-use crate::PartialPriceData;
+use crate::{
+    PartialConstantProductCurve, PartialDutchAuction, PartialFeeRecipient, PartialLinearCurve,
+    PartialPriceData, PartialTakerAllowlist,
+};
 
-#[derive(FromClvm, ToClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(FromClvm, ToClvm, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[clvm(list)]
 pub struct PartialOfferAssetInfo {
     pub asset_id: Option<Bytes32>,
@@ -45,8 +48,37 @@ pub struct PartialOfferHint<IP> {
     pub offered_asset_info: PartialOfferAssetInfo,
     pub requested_asset_info: PartialOfferAssetInfo,
     pub price_data: PartialPriceData,
-    pub maker_puzzle_hash: Bytes32,
+    pub clawback_puzzle_hash: Bytes32,
     pub inner_puzzle: IP,
     pub min_other_asset_amount_minus_one: u64,
+    // Optional fields are omitted when not set, so older hints still parse
+    #[clvm(default)]
+    pub dutch_auction: Option<PartialDutchAuction>,
+    // At most one curve may be set
+    #[clvm(default)]
+    pub linear_curve: Option<PartialLinearCurve>,
+    #[clvm(default)]
+    pub constant_product_curve: Option<PartialConstantProductCurve>,
+    // Adds the reclaim leaf (only used when the offer expires)
+    #[clvm(default)]
+    pub reclaimable: bool,
+    // Only set when fill proceeds don't go to the clawback puzzle hash
+    #[clvm(default)]
+    pub receiver_puzzle_hash: Option<Bytes32>,
+    // Extra clawback leaves (placed right before the clawback puzzle hash)
+    #[clvm(default)]
+    pub extra_leaves: Vec<Bytes32>,
+    // Timestamp before which no clawback leaf can be used
+    #[clvm(default)]
+    pub clawback_lock: Option<u64>,
+    // Fee split - can't be combined with curves
+    #[clvm(default)]
+    pub fee_recipients: Vec<PartialFeeRecipient>,
+    // Private offers
+    #[clvm(default)]
+    pub taker_allowlist: Option<PartialTakerAllowlist>,
+    // Co-signed offers (fill authorizer key)
+    #[clvm(default)]
+    pub fill_authorizer: Option<PublicKey>,
     // No #[clvm(rest)] here either
 }
@@ -0,0 +1,43 @@
+use std::borrow::Cow;
+
+use chia::{clvm_utils::TreeHash, protocol::Bytes32};
+use chia_wallet_sdk::types::Mod;
+use clvm_traits::{FromClvm, ToClvm};
+use hex_literal::hex;
+
+pub const RECLAIM_PUZZLE: [u8; 79] = hex!(
+    "
+    ff04ffff04ffff0151ffff04ff05ff808080ffff04ffff04ffff0149ffff04ff
+    0bff808080ffff04ffff04ffff0133ffff04ff02ffff04ff0bffff04ffff04ff
+    02ff8080ff8080808080ff80808080
+    "
+);
+
+pub const RECLAIM_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    7f53f43346f68101c9a750204b3ab0b90ca2ddfb25897146bd827dc48758f173
+    "
+));
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct ReclaimArgs {
+    pub maker_puzzle_hash: Bytes32,
+    pub expiration: u64,
+}
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct ReclaimSolution {
+    pub my_amount: u64,
+}
+
+impl Mod for ReclaimArgs {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&RECLAIM_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        RECLAIM_PUZZLE_HASH
+    }
+}
@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+
+use chia::{bls::PublicKey, clvm_utils::TreeHash, protocol::Bytes32};
+use chia_wallet_sdk::types::Mod;
+use clvm_traits::{FromClvm, ToClvm};
+use hex_literal::hex;
+
+pub const TAKER_ALLOWLIST_PUZZLE: [u8; 277] = hex!(
+    "
+    ff02ffff01ff04ffff04ffff0146ffff04ff81bfff808080ffff04ffff02ffff
+    03ff2fffff01ff02ffff03ffff02ff04ffff04ff02ffff04ff2fffff04ff05ff
+    8080808080ffff01ff04ffff0132ffff04ff2fffff04ff81bfff80808080ffff
+    01ff088080ff0180ffff01ff02ffff03ffff02ff04ffff04ff02ffff04ff5fff
+    ff04ff0bff8080808080ffff01ff04ffff013fffff04ffff0bff5fff81bf80ff
+    808080ffff01ff088080ff018080ff0180ffff02ff17ff81ff808080ffff04ff
+    ff01ffff02ffff03ff0bffff01ff02ffff03ffff09ff05ffff05ff0b8080ffff
+    01ff0101ffff01ff02ff04ffff04ff02ffff04ff05ffff04ffff06ff0b80ff80
+    8080808080ff0180ffff018080ff018080ff018080
+    "
+);
+
+pub const TAKER_ALLOWLIST_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    cf355bd44070115de45270f31e7e38dd89b38fdc74ba1bd8f53d4620e1c6b225
+    "
+));
+
+// Takers allowed to fill a private (OTC) partial offer
+#[derive(ToClvm, FromClvm, Debug, Clone, Default, PartialEq, Eq)]
+#[clvm(list)]
+pub struct PartialTakerAllowlist {
+    // AGG_SIG_ME of the partial coin id
+    pub keys: Vec<PublicKey>,
+    // puzzle announcement of the partial coin id
+    pub puzzle_hashes: Vec<Bytes32>,
+}
+
+impl PartialTakerAllowlist {
+    pub fn allows(&self, taker: &PartialTaker) -> bool {
+        match taker {
+            PartialTaker::Key(key) => self.keys.contains(key),
+            PartialTaker::PuzzleHash(puzzle_hash) => self.puzzle_hashes.contains(puzzle_hash),
+        }
+    }
+}
+
+// How a taker proves it's on the allowlist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialTaker {
+    // signs the partial coin id - AGG_SIG_ME
+    Key(PublicKey),
+    // spends a coin that announces the partial coin id
+    PuzzleHash(Bytes32),
+}
+
+impl PartialTaker {
+    pub fn solution<S>(&self, my_id: Bytes32, inner_solution: S) -> TakerAllowlistSolution<S> {
+        match self {
+            PartialTaker::Key(key) => TakerAllowlistSolution {
+                taker_key: Some(*key),
+                taker_puzzle_hash: None,
+                my_id,
+                inner_solution,
+            },
+            PartialTaker::PuzzleHash(puzzle_hash) => TakerAllowlistSolution {
+                taker_key: None,
+                taker_puzzle_hash: Some(*puzzle_hash),
+                my_id,
+                inner_solution,
+            },
+        }
+    }
+}
+
+#[derive(ToClvm, FromClvm, Debug, Clone, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct TakerAllowlistArgs<I> {
+    pub allowed_keys: Vec<PublicKey>,
+    pub allowed_puzzle_hashes: Vec<Bytes32>,
+    pub inner_puzzle: I,
+}
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct TakerAllowlistSolution<S> {
+    pub taker_key: Option<PublicKey>,
+    pub taker_puzzle_hash: Option<Bytes32>,
+    pub my_id: Bytes32,
+    #[clvm(rest)]
+    pub inner_solution: S,
+}
+
+impl<I> Mod for TakerAllowlistArgs<I> {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&TAKER_ALLOWLIST_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        TAKER_ALLOWLIST_PUZZLE_HASH
+    }
+}
@@ -0,0 +1,45 @@
+use std::borrow::Cow;
+
+use chia::clvm_utils::TreeHash;
+use chia_wallet_sdk::types::Mod;
+use clvm_traits::{FromClvm, ToClvm};
+use hex_literal::hex;
+
+pub const TIMELOCK_PUZZLE: [u8; 25] = hex!(
+    "
+    ff04ffff04ff02ffff04ff05ff808080ffff02ff0bff178080
+    "
+);
+
+pub const TIMELOCK_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "
+    3af787b9e1c144f8f23e002d5932b791a3c92709d67d245cb905cade9c3f6ebc
+    "
+));
+
+pub const ASSERT_SECONDS_RELATIVE_OPCODE: u8 = 80;
+pub const ASSERT_SECONDS_ABSOLUTE_OPCODE: u8 = 81;
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(curry)]
+pub struct TimelockArgs<I> {
+    pub condition_opcode: u8,
+    pub seconds: u64,
+    pub inner_puzzle: I,
+}
+
+#[derive(ToClvm, FromClvm, Debug, Clone, Copy, PartialEq, Eq)]
+#[clvm(list)]
+pub struct TimelockSolution<S> {
+    pub inner_solution: S,
+}
+
+impl<I> Mod for TimelockArgs<I> {
+    fn mod_reveal() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&TIMELOCK_PUZZLE)
+    }
+
+    fn mod_hash() -> TreeHash {
+        TIMELOCK_PUZZLE_HASH
+    }
+}